@@ -0,0 +1,101 @@
+// Generates rah(8) from the command line definition and rah.toml(5) from the config schema, so
+// that the man pages can't drift from the code.
+
+use {
+    clap_mangen::{
+        roff::{bold, italic, roman, Roff},
+        Man,
+    },
+    std::{
+        env, fs,
+        io::Result,
+        path::{Path, PathBuf},
+    },
+};
+
+// the messages are English here, the build script never picking a locale
+#[allow(dead_code)]
+#[path = "src/i18n.rs"]
+mod i18n;
+
+#[allow(dead_code)]
+#[path = "src/cli.rs"]
+mod cli;
+
+#[allow(dead_code)]
+#[path = "src/config_schema.rs"]
+mod config_schema;
+
+/// rah(8), along with a page for each subcommand.
+fn rah_pages(out_dir: &Path) -> Result<()> {
+    let mut cmd = cli::command().disable_help_subcommand(true);
+    cmd.build();
+
+    for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        Man::new(sub.clone()).section("8").generate_to(out_dir)?;
+    }
+    Man::new(cmd).section("8").generate_to(out_dir)?;
+
+    Ok(())
+}
+
+/// rah.toml(5), listing every config key.
+fn config_page(out_dir: &Path) -> Result<()> {
+    let mut page = Roff::new();
+    page.control("TH", ["rah.toml", "5"])
+        .control("SH", ["NAME"])
+        .text([roman("rah.toml - rah configuration file")])
+        .control("SH", ["SYNOPSIS"])
+        .text([
+            italic("/etc/rah.toml"),
+            roman(", or the file given to "),
+            bold("--config"),
+        ])
+        .control("SH", ["DESCRIPTION"])
+        .text([roman(
+            "rah reads its configuration from a TOML file. Every key is optional, unknown keys \
+             are ignored with a warning.",
+        )])
+        .control("SH", ["OPTIONS"]);
+
+    for key in config_schema::KEYS {
+        page.control("TP", [])
+            .text([bold(key.name), roman(" ("), italic(key.kind), roman(")")])
+            .text([roman(key.description)]);
+        if let Some(default) = key.default {
+            page.control("br", [])
+                .text([roman("Default : "), bold(default)]);
+        }
+    }
+
+    page.control("SH", ["SEE ALSO"]).text([
+        bold("rah"),
+        roman("(8), "),
+        bold("pacman.conf"),
+        roman("(5)"),
+    ]);
+
+    fs::write(out_dir.join("rah.toml.5"), page.render())
+}
+
+fn main() -> Result<()> {
+    for path in [
+        "src/cli.rs",
+        "src/i18n.rs",
+        "src/config_schema.rs",
+        "i18n/en/rah.ftl",
+    ] {
+        println!("cargo:rerun-if-changed={path}");
+    }
+    println!("cargo:rerun-if-env-changed=RAH_MAN_DIR");
+
+    // packagers can have the pages written somewhere easier to find than OUT_DIR
+    let out_dir = match env::var_os("RAH_MAN_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("man"),
+    };
+    fs::create_dir_all(&out_dir)?;
+
+    rah_pages(&out_dir)?;
+    config_page(&out_dir)
+}
@@ -0,0 +1,232 @@
+## Command line
+
+about = rah - the Rusty AUR Helper !
+arg-targets = Search the AUR and pick the packages to install among the results
+arg-config = Choose a specific config file
+arg-offline = Only use the local AUR metadata cache, never reach the network
+arg-jobs = Build up to N AUR packages that don't depend on each other at the same time
+arg-resume = Carry on with the transaction that failed or was aborted
+arg-json = Print machine-readable JSON instead of text, same as --format=json
+arg-format = Output format : text, json, ndjson or a template like '{"{"}name{"}"}'
+arg-color = Colorize the output
+arg-skip-distro-check = Run even if this doesn't look like an Arch-based system
+cmd-query = Query the local package database
+arg-query-search = Search for matching packages in the local package database
+arg-query-info = Get package info from the local package database
+cmd-completions = Print the shell completion script for SHELL
+arg-shell = Shell to complete rah's arguments in
+cmd-sync = Synchronize packages with the AUR
+arg-refresh = Download a fresh copy of the AUR metadata cache, and of pacman's databases when installing
+arg-sync-search = Search for matching packages in the AUR
+arg-sync-info = Get package info from the AUR
+arg-by = Field searched with -s
+arg-sortby = Sort search results, most voted/popular/recent first
+arg-reverse = Reverse the order of search results
+arg-limit = Only show the first N search results
+arg-no-outdated = Hide packages flagged out of date from search results
+arg-maintained-only = Hide orphaned packages from search results
+arg-min-votes = Hide packages with less than N votes from search results
+arg-needed = Don't reinstall packages that are already up to date
+arg-asdeps = Install packages as dependencies
+arg-asexplicit = Install packages as explicitly installed
+arg-noconfirm = Don't ask for any confirmation
+arg-print-plan = Only show what would be installed, built and removed
+arg-graph = Show the resolved dependency tree instead of the package info
+arg-dot = Print the dependency graph in Graphviz DOT format
+cmd-deptree = Show the resolved dependency tree of AUR packages
+arg-required-by = Show the installed and AUR packages depending on the packages, directly or not
+cmd-whyneeded = Show the installed and AUR packages depending on packages, directly or not
+arg-packages = packages
+command-not-found = Command "{ $command }" not found.
+
+## Execution context
+
+not-arch =
+    Your distro is probably not an Arch-based distro, rah shouldn't be used on it. If untrue, please file an issue here https://github.com/kStor2poche/rah/issues
+    Aborting...
+context-checks-failed = some execution context checks failed :
+context-checks-skip = These checks can be skipped with --skip-distro-check or skip_distro_check = true in the config.
+check-ok = [ok]
+check-failed = [failed]
+check-os-release-unreadable = neither /etc/os-release nor /usr/lib/os-release could be read
+check-os-release = { $path } has ID={ $id } and ID_LIKE={ $id_like }, one of which should be "arch"
+check-found-at = found at { $path }
+check-not-in-path = not found in PATH
+check-libalpm-missing = not found in the usual library directories
+
+## Messages
+
+warning = warning
+error = error
+db-register-failed = Could not register db { $repo } : { $error }
+command-killed = { $command } did not exit or was killed by a signal
+command-failed = { $command } exited with status { $code }
+packages-not-found = { $count ->
+    [one] Package not found : { $packages }
+   *[other] Packages not found : { $packages }
+}
+package-not-installed = Package "{ $name }" is not installed
+building = Building { $pkgbase }...
+local-repo-ready = Built packages are available in local repository { $repo }
+found-packages = Found { $count ->
+    [one] { $count } package
+   *[other] { $count } packages
+}
+found-info = Found info for { $count ->
+    [one] { $count } package
+   *[other] { $count } packages
+}
+no-package-found = No package found
+nothing-to-do = Nothing to do
+no-targets = No targets specified
+menu-prompt = Packages to install (eg: 1 2 3, 1-3 or ^4)
+invalid-selection = Invalid selection "{ $selection }", expected numbers between 1 and { $max }
+
+## Transaction plan
+
+plan-repo = { $count ->
+    [one] Repo package to install ({ $count })
+   *[other] Repo packages to install ({ $count })
+}
+plan-aur = { $count ->
+    [one] AUR package to build ({ $count })
+   *[other] AUR packages to build, in order ({ $count })
+}
+plan-remove-after = { $count ->
+    [one] Make dependency to remove afterward ({ $count })
+   *[other] Make dependencies to remove afterward ({ $count })
+}
+plan-conflict-installed = { $package } conflicts with the installed { $other }
+plan-conflict-planned = { $package } conflicts with { $other }, also to be installed
+plan-replaces = { $package } replaces the installed { $other }
+plan-sizes = Download size : { $download }, installed size : { $installed }
+plan-proceed = Proceed with the installation ?
+remove-make-deps = { $count ->
+    [one] Remove the make dependency ?
+   *[other] Remove the { $count } make dependencies ?
+}
+reason-depends = dependency
+reason-make_depends = make dependency
+reason-opt_depends = optional dependency
+reason-check_depends = check dependency
+answer-yes = y
+answer-no = n
+answer-replace = r
+answer-skip = s
+answer-abort = a
+conflict-prompt = { $package } and { $other } are in conflict. Replace { $other } or abort ?
+conflict-prompt-skip = { $package } and { $other } are in conflict. Replace { $other }, skip { $package } or abort ?
+conflict-aborted = Aborted because of the conflict between { $package } and { $other }
+
+## Transactions
+
+build-failed = Building { $pkgbase } failed, what now ?
+recovery-edit = Edit the PKGBUILD and retry
+recovery-retry = Retry
+recovery-skip = Skip it, along with the packages depending on it
+recovery-shell = Open a shell in the build directory, then retry
+recovery-abort = Abort
+transaction-saved = The transaction was saved, rah --resume carries on from { $pkgbase }
+no-transaction = No transaction to resume
+resuming = Resuming the transaction ({ $built } built, { $pending } to build)
+unfinished-transaction = A transaction was left unfinished, what now ?
+unfinished-resume = Resume it instead
+unfinished-discard = Discard it, it can't be resumed afterwards
+unfinished-abort = Abort
+unfinished-aborted = Aborted, the unfinished transaction is kept for rah --resume
+unfinished-noconfirm = A transaction was left unfinished, rah --resume carries it on, or run without --noconfirm to discard it
+skipped-packages = { $count ->
+    [one] Skipped { $packages }
+   *[other] Skipped { $count } packages : { $packages }
+}
+
+## Build failure diagnosis
+
+build-log = Full build log : { $path }
+diagnosis-unknown-keys = The sources are signed with PGP keys missing from your keyring : { $keys }
+fix-unknown-keys = Check the keys against the PKGBUILD's validpgpkeys, then import them with gpg --recv-keys { $keys }
+import-keys-prompt = Import the missing keys and retry ?
+diagnosis-checksums = Some sources don't match their checksums : { $files }
+fix-checksums = The sources may have changed upstream, check the comments of { $pkgbase } on the AUR before editing the checksums (updpkgsums)
+diagnosis-missing-deps = Some dependencies are missing : { $deps }
+fix-missing-deps = Install them (rah -S --asdeps { $deps }) or check their names in the PKGBUILD
+diagnosis-no-space = The disk ran out of space
+fix-no-space = Free some space, e.g. by cleaning the package caches (rah -Sc), or build elsewhere by setting cache_path
+diagnosis-not-found = Some sources can't be downloaded anymore (404) : { $files }
+fix-not-found = The source URLs are probably outdated, flag { $pkgbase } out of date on the AUR or fix the source array of the PKGBUILD
+missing-keys = { $count ->
+    [one] The sources of { $pkgbase } are signed with a PGP key missing from your keyring :
+   *[other] The sources of { $pkgbase } are signed with { $count } PGP keys missing from your keyring :
+}
+unknown-uid = (unknown owner)
+import-keys-pkgbase = Import them ?
+
+## Dependency tree
+
+graph-repo = repo
+graph-aur = aur
+graph-installed = installed
+graph-missing = not found
+graph-seen = see above
+required-by-header = { $count ->
+    [0] Nothing requires { $package }
+    [one] { $count } package requires { $package }
+   *[other] { $count } packages require { $package }
+}
+
+## Search flags
+
+flag-installed = installed
+flag-other-version = other ver. installed ({ $version })
+flag-out-of-date = out of date since { $since }, last update { $last_modified }
+
+## Package info
+
+info-name = Name
+info-version = Version
+info-package-base = Package base
+info-votes = Votes
+info-popularity = Popularity
+info-description = Description
+info-submitter = Submitter
+info-maintainer = Maintainer
+info-co-maintainers = Co-maintainers
+info-out-of-date = Out of date
+info-first-submitted = First submitted
+info-last-updated = Last updated
+info-git-clone-url = Git clone URL
+info-upstream-url = Upstream URL
+info-tarball-url = Tarball URL
+info-licenses = Licenses
+info-groups = Groups
+info-provides = Provides
+info-depends = Depends
+info-opt-depends = Opt. dependencies
+info-make-depends = Make dependencies
+info-check-depends = Check dependencies
+info-conflicts = Conflicts
+info-replaces = Replaces
+info-keywords = Keywords
+info-install-reason = Install reason
+info-installed-size = Installed size
+info-required-by = Required by
+
+flagged-out-of-date = Flagged out of date since { $since }
+not-out-of-date = Not flagged out of date
+explicitly-installed = Explicitly installed
+installed-as-dep = Installed as a dependency
+no-description = No description.
+no-submitter = No submitter.
+no-maintainer = No maintainer.
+no-co-maintainers = No co-maintainers.
+no-upstream-url = No upstream URL.
+no-groups = No groups.
+no-provides = No provides.
+no-depends = No dependencies.
+no-opt-depends = No optional dependencies.
+no-make-depends = No make dependencies.
+no-check-depends = No check dependencies.
+no-conflicts = No conflicts.
+no-replaces = No replaces.
+no-keywords = No keywords.
+not-required = Not required.
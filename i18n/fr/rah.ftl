@@ -0,0 +1,232 @@
+## Ligne de commande
+
+about = rah - le Rusty AUR Helper !
+arg-targets = Chercher dans l'AUR et choisir les paquets à installer parmi les résultats
+arg-config = Choisissez un fichier de configuration spécifique
+arg-offline = N'utiliser que le cache local des métadonnées de l'AUR, sans accès au réseau
+arg-jobs = Compiler jusqu'à N paquets AUR indépendants les uns des autres en même temps
+arg-resume = Reprendre la transaction qui a échoué ou a été annulée
+arg-json = Afficher du JSON lisible par une machine plutôt que du texte, comme --format=json
+arg-format = Format de sortie : text, json, ndjson ou un modèle comme '{"{"}name{"}"}'
+arg-color = Colorer la sortie
+arg-skip-distro-check = Continuer même si le système ne semble pas basé sur Arch
+cmd-query = Faire une requête à la base de données locale
+arg-query-search = Chercher pour les paquets correspondants dans la base de donnée locale
+arg-query-info = Chercher l'info des paquets indexés localement
+cmd-completions = Afficher le script de complétion pour le shell SHELL
+arg-shell = Shell dans lequel compléter les arguments de rah
+cmd-sync = Synchroniser les paquets avec l'AUR
+arg-refresh = Télécharger une copie à jour du cache des métadonnées de l'AUR, et des bases de données de pacman lors d'une installation
+arg-sync-search = Chercher les paquets correspondants dans l'AUR
+arg-sync-info = Chercher l'info des paquets dans l'AUR
+arg-by = Champ dans lequel chercher avec -s
+arg-sortby = Trier les résultats, les plus votés/populaires/récents en premier
+arg-reverse = Inverser l'ordre des résultats
+arg-limit = N'afficher que les N premiers résultats
+arg-no-outdated = Masquer les paquets marqués comme périmés
+arg-maintained-only = Masquer les paquets orphelins
+arg-min-votes = Masquer les paquets ayant moins de N votes
+arg-needed = Ne pas réinstaller les paquets déjà à jour
+arg-asdeps = Installer les paquets en tant que dépendances
+arg-asexplicit = Installer les paquets en tant que paquets explicitement installés
+arg-noconfirm = Ne demander aucune confirmation
+arg-print-plan = Afficher seulement ce qui serait installé, compilé et supprimé
+arg-graph = Afficher l'arbre des dépendances résolues plutôt que les informations du paquet
+arg-dot = Afficher le graphe des dépendances au format DOT de Graphviz
+cmd-deptree = Afficher l'arbre des dépendances résolues de paquets AUR
+arg-required-by = Afficher les paquets installés et AUR dépendant des paquets, directement ou non
+cmd-whyneeded = Afficher les paquets installés et AUR dépendant de paquets, directement ou non
+arg-packages = paquets
+command-not-found = Commande « { $command } » introuvable.
+
+## Contexte d'exécution
+
+not-arch =
+    Votre distribution n'est pas détectée comme étant basée sur arch, rah ne devrait pas être utilisé avec. Si ce n'est pas le cas, reportez s'il vous plaît le problème ici https://github.com/kStor2poche/rah/issues
+    Arrêt...
+context-checks-failed = certaines vérifications du contexte d'exécution ont échoué :
+context-checks-skip = Ces vérifications peuvent être ignorées avec --skip-distro-check ou skip_distro_check = true dans la configuration.
+check-ok = [ok]
+check-failed = [échec]
+check-os-release-unreadable = ni /etc/os-release ni /usr/lib/os-release n'ont pu être lus
+check-os-release = { $path } indique ID={ $id } et ID_LIKE={ $id_like }, l'un des deux devrait être « arch »
+check-found-at = trouvé à { $path }
+check-not-in-path = introuvable dans le PATH
+check-libalpm-missing = introuvable dans les dossiers de bibliothèques habituels
+
+## Messages
+
+warning = attention
+error = erreur
+db-register-failed = Impossible d'enregistrer la base { $repo } : { $error }
+command-killed = { $command } ne s'est pas terminé ou a été tué par un signal
+command-failed = { $command } s'est terminé avec le statut { $code }
+packages-not-found = { $count ->
+    [one] Paquet introuvable : { $packages }
+   *[other] Paquets introuvables : { $packages }
+}
+package-not-installed = Le paquet « { $name } » n'est pas installé
+building = Compilation de { $pkgbase }...
+local-repo-ready = Les paquets compilés sont disponibles dans le dépôt local { $repo }
+found-packages = { $count ->
+    [one] { $count } paquet trouvé
+   *[other] { $count } paquets trouvés
+}
+found-info = { $count ->
+    [one] Info trouvée pour { $count } paquet
+   *[other] Info trouvée pour { $count } paquets
+}
+no-package-found = Aucun paquet trouvé
+nothing-to-do = Rien à faire
+no-targets = Aucune cible indiquée
+menu-prompt = Paquets à installer (ex : 1 2 3, 1-3 ou ^4)
+invalid-selection = Sélection « { $selection } » invalide, les nombres doivent être compris entre 1 et { $max }
+
+## Plan de la transaction
+
+plan-repo = { $count ->
+    [one] Paquet des dépôts à installer ({ $count })
+   *[other] Paquets des dépôts à installer ({ $count })
+}
+plan-aur = { $count ->
+    [one] Paquet AUR à compiler ({ $count })
+   *[other] Paquets AUR à compiler, dans l'ordre ({ $count })
+}
+plan-remove-after = { $count ->
+    [one] Dépendance de compilation à supprimer ensuite ({ $count })
+   *[other] Dépendances de compilation à supprimer ensuite ({ $count })
+}
+plan-conflict-installed = { $package } est en conflit avec { $other }, installé
+plan-conflict-planned = { $package } est en conflit avec { $other }, installé lui aussi
+plan-replaces = { $package } remplace { $other }, installé
+plan-sizes = Taille du téléchargement : { $download }, taille une fois installé : { $installed }
+plan-proceed = Procéder à l'installation ?
+remove-make-deps = { $count ->
+    [one] Supprimer la dépendance de compilation ?
+   *[other] Supprimer les { $count } dépendances de compilation ?
+}
+reason-depends = dépendance
+reason-make_depends = dépendance de compilation
+reason-opt_depends = dépendance optionnelle
+reason-check_depends = dépendance de test
+answer-yes = o
+answer-no = n
+answer-replace = r
+answer-skip = i
+answer-abort = a
+conflict-prompt = { $package } et { $other } sont en conflit. Remplacer { $other } ou annuler ?
+conflict-prompt-skip = { $package } et { $other } sont en conflit. Remplacer { $other }, ignorer { $package } ou annuler ?
+conflict-aborted = Annulé à cause du conflit entre { $package } et { $other }
+
+## Transactions
+
+build-failed = La compilation de { $pkgbase } a échoué, que faire ?
+recovery-edit = Modifier le PKGBUILD et réessayer
+recovery-retry = Réessayer
+recovery-skip = L'ignorer, ainsi que les paquets qui en dépendent
+recovery-shell = Ouvrir un shell dans le dossier de compilation, puis réessayer
+recovery-abort = Annuler
+transaction-saved = La transaction a été enregistrée, rah --resume reprend à partir de { $pkgbase }
+no-transaction = Aucune transaction à reprendre
+resuming = Reprise de la transaction ({ $built } compilés, { $pending } à compiler)
+unfinished-transaction = Une transaction est restée inachevée, que faire ?
+unfinished-resume = La reprendre à la place
+unfinished-discard = L'abandonner, elle ne pourra plus être reprise
+unfinished-abort = Annuler
+unfinished-aborted = Annulé, la transaction inachevée est conservée pour rah --resume
+unfinished-noconfirm = Une transaction est restée inachevée, rah --resume la reprend, sinon relancez sans --noconfirm pour l'abandonner
+skipped-packages = { $count ->
+    [one] { $packages } ignoré
+   *[other] { $count } paquets ignorés : { $packages }
+}
+
+## Diagnostic des échecs de compilation
+
+build-log = Journal complet de la compilation : { $path }
+diagnosis-unknown-keys = Les sources sont signées avec des clés PGP absentes de votre trousseau : { $keys }
+fix-unknown-keys = Vérifiez les clés d'après le validpgpkeys du PKGBUILD, puis importez-les avec gpg --recv-keys { $keys }
+import-keys-prompt = Importer les clés manquantes et réessayer ?
+diagnosis-checksums = Certaines sources ne correspondent pas à leurs sommes de contrôle : { $files }
+fix-checksums = Les sources ont peut-être changé en amont, lisez les commentaires de { $pkgbase } sur l'AUR avant de modifier les sommes de contrôle (updpkgsums)
+diagnosis-missing-deps = Des dépendances sont manquantes : { $deps }
+fix-missing-deps = Installez-les (rah -S --asdeps { $deps }) ou vérifiez leurs noms dans le PKGBUILD
+diagnosis-no-space = Le disque est plein
+fix-no-space = Libérez de l'espace, par exemple en nettoyant les caches de paquets (rah -Sc), ou compilez ailleurs en changeant cache_path
+diagnosis-not-found = Certaines sources ne peuvent plus être téléchargées (404) : { $files }
+fix-not-found = Les URL des sources sont sans doute obsolètes, signalez { $pkgbase } comme périmé sur l'AUR ou corrigez le tableau source du PKGBUILD
+missing-keys = { $count ->
+    [one] Les sources de { $pkgbase } sont signées avec une clé PGP absente de votre trousseau :
+   *[other] Les sources de { $pkgbase } sont signées avec { $count } clés PGP absentes de votre trousseau :
+}
+unknown-uid = (propriétaire inconnu)
+import-keys-pkgbase = Les importer ?
+
+## Arbre des dépendances
+
+graph-repo = dépôt
+graph-aur = aur
+graph-installed = installé
+graph-missing = introuvable
+graph-seen = voir plus haut
+required-by-header = { $count ->
+    [0] Rien ne dépend de { $package }
+    [one] { $count } paquet dépend de { $package }
+   *[other] { $count } paquets dépendent de { $package }
+}
+
+## Indicateurs de recherche
+
+flag-installed = installé
+flag-other-version = autre version installée ({ $version })
+flag-out-of-date = périmé depuis le { $since }, dernière mise à jour le { $last_modified }
+
+## Informations sur les paquets
+
+info-name = Nom
+info-version = Version
+info-package-base = Paquet de base
+info-votes = Votes
+info-popularity = Popularité
+info-description = Description
+info-submitter = Soumis par
+info-maintainer = Mainteneur
+info-co-maintainers = Co-mainteneurs
+info-out-of-date = Périmé
+info-first-submitted = Première soumission
+info-last-updated = Dernière mise à jour
+info-git-clone-url = URL de clone Git
+info-upstream-url = URL du projet
+info-tarball-url = URL de l'archive
+info-licenses = Licences
+info-groups = Groupes
+info-provides = Fournit
+info-depends = Dépend de
+info-opt-depends = Dépendances opt.
+info-make-depends = Dépendances de compilation
+info-check-depends = Dépendances de test
+info-conflicts = Conflits
+info-replaces = Remplace
+info-keywords = Mots-clés
+info-install-reason = Motif d'installation
+info-installed-size = Taille installée
+info-required-by = Requis par
+
+flagged-out-of-date = Marqué comme périmé depuis le { $since }
+not-out-of-date = Non marqué comme périmé
+explicitly-installed = Installé explicitement
+installed-as-dep = Installé comme dépendance
+no-description = Aucune description.
+no-submitter = Aucun auteur.
+no-maintainer = Aucun mainteneur.
+no-co-maintainers = Aucun co-mainteneur.
+no-upstream-url = Aucune URL de projet.
+no-groups = Aucun groupe.
+no-provides = Ne fournit rien.
+no-depends = Aucune dépendance.
+no-opt-depends = Aucune dépendance optionnelle.
+no-make-depends = Aucune dépendance de compilation.
+no-check-depends = Aucune dépendance de test.
+no-conflicts = Aucun conflit.
+no-replaces = Ne remplace rien.
+no-keywords = Aucun mot-clé.
+not-required = Requis par aucun paquet.
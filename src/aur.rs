@@ -0,0 +1,89 @@
+use {
+    crate::{aur_cache, aur_cache::AurCache, config::Config},
+    anyhow::{anyhow, Result},
+    log::{info, warn},
+    raur::{Raur, SearchBy},
+};
+
+/// Entry point for every AUR query, answering from the metadata cache when it is enabled (or
+/// when running offline) and from the RPC otherwise.
+pub struct Aur {
+    handle: raur::Handle,
+    cache: Option<AurCache>,
+    offline: bool,
+}
+
+impl Aur {
+    /// Set up the AUR access according to the config, refreshing the metadata cache if it is
+    /// stale (or if `force_refresh` is set) and we're allowed to reach the network.
+    pub async fn new(conf: &Config, force_refresh: bool) -> Result<Self> {
+        let handle = raur::Handle::new_with_url(conf.aur_endpoint("rpc/"));
+
+        if conf.offline && force_refresh {
+            return Err(anyhow!(
+                "Cannot refresh the AUR metadata cache while offline"
+            ));
+        }
+
+        if !conf.aur_cache && !conf.offline {
+            return Ok(Aur {
+                handle,
+                cache: None,
+                offline: false,
+            });
+        }
+
+        if !conf.offline && (force_refresh || aur_cache::is_stale(conf)) {
+            if let Err(err) = aur_cache::refresh(conf).await {
+                // an outdated cache is still better than no cache at all
+                if aur_cache::age(conf).is_none() {
+                    return Err(err);
+                }
+                warn!("Could not refresh the AUR metadata cache : {err}");
+            }
+        }
+
+        let cache = AurCache::load(conf)?;
+        info!("Using AUR metadata cache ({} packages)", cache.len());
+
+        Ok(Aur {
+            handle,
+            cache: Some(cache),
+            offline: conf.offline,
+        })
+    }
+
+    pub async fn info<S: AsRef<str> + Send + Sync>(
+        &self,
+        names: &[S],
+    ) -> Result<Vec<raur::Package>> {
+        let Some(cache) = &self.cache else {
+            return Ok(self.handle.info(names).await?);
+        };
+
+        let mut hits = cache.info(names);
+
+        // packages submitted since the last refresh are only known to the RPC
+        if hits.len() != names.len() && !self.offline {
+            let missing = names
+                .iter()
+                .map(|name| name.as_ref())
+                .filter(|name| !hits.iter().any(|hit| hit.name == *name))
+                .collect::<Vec<_>>();
+            hits.extend(self.handle.info(&missing).await?);
+        }
+
+        Ok(hits)
+    }
+
+    pub async fn search_by(&self, query: &str, by: SearchBy) -> Result<Vec<raur::Package>> {
+        match &self.cache {
+            Some(cache) => Ok(cache.search_by(query, by)),
+            None => Ok(self.handle.search_by(query, by).await?),
+        }
+    }
+
+    pub async fn search(&self, query: &str) -> Result<Vec<raur::Package>> {
+        self.search_by(query, SearchBy::NameDesc).await
+    }
+}
@@ -0,0 +1,189 @@
+use {
+    crate::{config::Config, dep_tree::parse_dependency},
+    anyhow::{Context, Result},
+    flate2::read::GzDecoder,
+    log::{debug, info},
+    raur::SearchBy,
+    serde::Deserialize,
+    std::{
+        collections::HashMap,
+        fs,
+        io::Read,
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    },
+};
+
+const META_FILE: &str = "packages-meta-ext-v1.json";
+
+/// Local copy of the AUR's bulk metadata dump, indexed by name and provides so that search,
+/// info and dependency lookups don't need to hit the RPC.
+pub struct AurCache {
+    pkgs: Vec<raur::Package>,
+    by_name: HashMap<String, usize>,
+    by_provides: HashMap<String, Vec<usize>>,
+}
+
+pub fn cache_dir(conf: &Config) -> PathBuf {
+    Path::new(&conf.cache_path).join("aur")
+}
+
+fn meta_file(conf: &Config) -> PathBuf {
+    cache_dir(conf).join(META_FILE)
+}
+
+/// Age of the metadata dump, or `None` if it was never downloaded.
+pub fn age(conf: &Config) -> Option<Duration> {
+    let modified = fs::metadata(meta_file(conf)).ok()?.modified().ok()?;
+    SystemTime::now().duration_since(modified).ok()
+}
+
+pub fn is_stale(conf: &Config) -> bool {
+    match age(conf) {
+        None => true,
+        Some(age) => age > Duration::from_secs(conf.aur_cache_max_age * 3600),
+    }
+}
+
+/// Download the metadata dump and replace the cached one.
+pub async fn refresh(conf: &Config) -> Result<()> {
+    let url = conf.aur_endpoint(&format!("{META_FILE}.gz"));
+    info!("Downloading AUR metadata from {url}...");
+
+    let data = reqwest::get(url).await?.error_for_status()?.bytes().await?;
+
+    // the dump may or may not have been transparently decompressed on the way
+    let json = if data.starts_with(&[0x1f, 0x8b]) {
+        let mut json = Vec::new();
+        GzDecoder::new(&data[..]).read_to_end(&mut json)?;
+        json
+    } else {
+        data.to_vec()
+    };
+
+    // make sure what we got is usable before replacing the previous dump
+    serde_json::from_slice::<Vec<raur::Package>>(&json)
+        .context("Downloaded AUR metadata is invalid")?;
+
+    fs::create_dir_all(cache_dir(conf))?;
+    let tmp = meta_file(conf).with_extension("json.part");
+    fs::write(&tmp, &json)?;
+    fs::rename(&tmp, meta_file(conf))?;
+
+    Ok(())
+}
+
+/// Names of the packages in the metadata dump, without parsing the rest of it, for shell
+/// completion.
+pub fn names(conf: &Config) -> Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct Name {
+        #[serde(rename = "Name")]
+        name: String,
+    }
+
+    let data = fs::read(meta_file(conf))?;
+    let names = serde_json::from_slice::<Vec<Name>>(&data)?;
+
+    Ok(names.into_iter().map(|pkg| pkg.name).collect())
+}
+
+impl AurCache {
+    pub fn load(conf: &Config) -> Result<Self> {
+        let path = meta_file(conf);
+        let data = fs::read(&path).with_context(|| {
+            format!("No AUR metadata cache at {path:?}, run rah once without --offline")
+        })?;
+        let pkgs = serde_json::from_slice::<Vec<raur::Package>>(&data)
+            .with_context(|| format!("Invalid AUR metadata cache at {path:?}"))?;
+
+        debug!("Loaded {} packages from the AUR metadata cache", pkgs.len());
+
+        Ok(Self::from_pkgs(pkgs))
+    }
+
+    pub fn from_pkgs(pkgs: Vec<raur::Package>) -> Self {
+        let mut by_name = HashMap::new();
+        let mut by_provides: HashMap<String, Vec<usize>> = HashMap::new();
+
+        for (i, pkg) in pkgs.iter().enumerate() {
+            by_name.insert(pkg.name.clone(), i);
+            by_provides.entry(pkg.name.clone()).or_default().push(i);
+            for provide in &pkg.provides {
+                let (name, _) = parse_dependency(provide);
+                by_provides.entry(name.to_string()).or_default().push(i);
+            }
+        }
+
+        AurCache {
+            pkgs,
+            by_name,
+            by_provides,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pkgs.len()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&raur::Package> {
+        self.by_name.get(name).map(|&i| &self.pkgs[i])
+    }
+
+    pub fn info<S: AsRef<str>>(&self, names: &[S]) -> Vec<raur::Package> {
+        names
+            .iter()
+            .filter_map(|name| self.get(name.as_ref()))
+            .cloned()
+            .collect()
+    }
+
+    pub fn search_by(&self, query: &str, by: SearchBy) -> Vec<raur::Package> {
+        if matches!(by, SearchBy::Provides) {
+            let mut idx = self.by_provides.get(query).cloned().unwrap_or_default();
+            idx.dedup();
+            return idx.into_iter().map(|i| self.pkgs[i].clone()).collect();
+        }
+
+        let query = query.to_lowercase();
+        self.pkgs
+            .iter()
+            .filter(|pkg| matches(pkg, &query, &by))
+            .cloned()
+            .collect()
+    }
+}
+
+fn is_user(user: Option<&str>, query: &str) -> bool {
+    user.is_some_and(|user| user.to_lowercase() == query)
+}
+
+fn has_dep(deps: &[String], query: &str) -> bool {
+    deps.iter().any(|dep| parse_dependency(dep).0 == query)
+}
+
+/// Mimics the AUR RPC's matching rules for each search field. `query` is expected lowercase.
+fn matches(pkg: &raur::Package, query: &str, by: &SearchBy) -> bool {
+    match by {
+        SearchBy::Name => pkg.name.to_lowercase().contains(query),
+        SearchBy::NameDesc => {
+            pkg.name.to_lowercase().contains(query)
+                || pkg
+                    .description
+                    .as_ref()
+                    .is_some_and(|desc| desc.to_lowercase().contains(query))
+        }
+        SearchBy::Maintainer => is_user(pkg.maintainer.as_deref(), query),
+        SearchBy::Submitter => is_user(pkg.submitter.as_deref(), query),
+        SearchBy::CoMaintainers => pkg.co_maintainers.iter().any(|m| is_user(Some(m), query)),
+        SearchBy::Depends => has_dep(&pkg.depends, query),
+        SearchBy::MakeDepends => has_dep(&pkg.make_depends, query),
+        SearchBy::OptDepends => has_dep(&pkg.opt_depends, query),
+        SearchBy::CheckDepends => has_dep(&pkg.check_depends, query),
+        SearchBy::Conflicts => has_dep(&pkg.conflicts, query),
+        SearchBy::Replaces => has_dep(&pkg.replaces, query),
+        SearchBy::Provides => pkg.name == query || has_dep(&pkg.provides, query),
+        SearchBy::Keywords => pkg.keywords.iter().any(|k| k.to_lowercase() == query),
+        SearchBy::Groups => pkg.groups.iter().any(|g| g == query),
+    }
+}
@@ -0,0 +1,149 @@
+use {
+    crate::{
+        aur::Aur,
+        dep_tree::{parse_dependency, satisfies},
+    },
+    anyhow::Result,
+    futures::{stream, StreamExt},
+    log::{debug, trace},
+    raur::SearchBy,
+    std::collections::HashMap,
+};
+
+/// Batches, parallelizes and memoizes the AUR lookups made while resolving a transaction.
+///
+/// Dependencies are resolved a whole tree level at a time : exact name hits are looked up in a
+/// single batched info request, and only the remaining ones go through (concurrent) provides
+/// searches.
+pub struct AurScheduler<'a> {
+    aur: &'a Aur,
+    concurrency: usize,
+    /// Full package info, by package name
+    infos: HashMap<String, raur::Package>,
+    /// Package chosen to satisfy a dependency, `None` if nothing in the AUR does
+    resolved: HashMap<String, Option<String>>,
+}
+
+impl<'a> AurScheduler<'a> {
+    pub fn new(aur: &'a Aur, concurrency: usize) -> Self {
+        AurScheduler {
+            aur,
+            concurrency: concurrency.max(1),
+            infos: HashMap::new(),
+            resolved: HashMap::new(),
+        }
+    }
+
+    /// Package satisfying `dep`, if it was resolved through [`Self::resolve`].
+    pub fn get(&self, dep: &str) -> Option<&raur::Package> {
+        self.resolved
+            .get(dep)?
+            .as_ref()
+            .and_then(|name| self.infos.get(name))
+    }
+
+    /// Fetch full info for `names`, only asking the AUR for the ones not seen yet.
+    pub async fn info<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Vec<raur::Package>> {
+        let mut unknown = names
+            .iter()
+            .map(|name| name.as_ref())
+            .filter(|name| !self.infos.contains_key(*name))
+            .collect::<Vec<_>>();
+        unknown.sort();
+        unknown.dedup();
+
+        if !unknown.is_empty() {
+            trace!("batched info request for {unknown:?}");
+            for pkg in self.aur.info(&unknown).await? {
+                self.infos.insert(pkg.name.clone(), pkg);
+            }
+        }
+
+        Ok(names
+            .iter()
+            .filter_map(|name| self.infos.get(name.as_ref()))
+            .cloned()
+            .collect())
+    }
+
+    /// Find AUR packages satisfying each of `deps`.
+    pub async fn resolve(&mut self, deps: &[String]) -> Result<()> {
+        let mut pending = deps
+            .iter()
+            .filter(|dep| !self.resolved.contains_key(*dep))
+            .cloned()
+            .collect::<Vec<_>>();
+        pending.sort();
+        pending.dedup();
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        // exact name hits first
+        let names = pending
+            .iter()
+            .map(|dep| parse_dependency(dep).0)
+            .collect::<Vec<_>>();
+        let hits = self.info(&names).await?;
+
+        pending.retain(|dep| {
+            match hits.iter().find(|hit| {
+                hit.name == parse_dependency(dep).0 && satisfies(&hit.name, &hit.version, &[], dep)
+            }) {
+                Some(hit) => {
+                    self.resolved.insert(dep.clone(), Some(hit.name.clone()));
+                    false
+                }
+                None => true,
+            }
+        });
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        // then provides searches for whatever is left
+        debug!(
+            "searching providers for {} dependencies, {} at a time",
+            pending.len(),
+            self.concurrency
+        );
+        let aur = self.aur;
+        let searches = stream::iter(pending.iter())
+            .map(|dep| async move {
+                let res = aur
+                    .search_by(parse_dependency(dep).0, SearchBy::Provides)
+                    .await;
+                (dep, res)
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut candidates = HashMap::new();
+        for (dep, res) in searches {
+            candidates.insert(
+                dep.clone(),
+                res?.into_iter().map(|pkg| pkg.name).collect::<Vec<_>>(),
+            );
+        }
+
+        // search results don't carry provides, so get the full info of every candidate
+        let candidate_names = candidates.values().flatten().cloned().collect::<Vec<_>>();
+        self.info(&candidate_names).await?;
+
+        for (dep, names) in candidates {
+            let best = names
+                .iter()
+                .filter_map(|name| self.infos.get(name))
+                .filter(|pkg| satisfies(&pkg.name, &pkg.version, &pkg.provides, &dep))
+                .max_by(|a, b| a.num_votes.cmp(&b.num_votes))
+                .map(|pkg| pkg.name.clone());
+            trace!("{dep} resolved to {best:?}");
+            self.resolved.insert(dep, best);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+use {
+    crate::tr,
+    clap::{Arg, ArgAction, Command},
+};
+
+pub const VERSION: &str = "0.0.1";
+
+/// Shells completion scripts can be generated for.
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// pacman operations, by short flag and long name.
+const OPERATIONS: &[(char, &str)] = &[
+    ('D', "database"),
+    ('F', "files"),
+    ('Q', "query"),
+    ('R', "remove"),
+    ('S', "sync"),
+    ('T', "deptest"),
+    ('U', "upgrade"),
+];
+
+/// pacman options taking a value, which mustn't be mistaken for targets or flags.
+const PACMAN_VALUE_SHORTS: &[char] = &['b', 'r'];
+const PACMAN_VALUE_LONGS: &[&str] = &[
+    "arch",
+    "assume-installed",
+    "cachedir",
+    "color",
+    "config",
+    "dbpath",
+    "gpgdir",
+    "hookdir",
+    "ignore",
+    "ignoregroup",
+    "logfile",
+    "overwrite",
+    "print-format",
+    "root",
+    "sysroot",
+];
+
+/// rah's own options that pacman also understands, kept when forwarding a command to it.
+const SHARED_OPTIONS: &[&str] = &["color"];
+
+/// rah's command line. `-S` and `-Q` are subcommands so that they get their own help, the
+/// rest of pacman's operations are only understood by `PacmanArgs`.
+pub fn command() -> Command {
+    Command::new("rah")
+        .about(tr!("about"))
+        .version(VERSION)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("targets")
+                .help(tr!("arg-targets"))
+                .action(ArgAction::Set)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help(tr!("arg-config")),
+        )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .help(tr!("arg-offline"))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help(tr!("arg-json"))
+                .conflicts_with("format")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help(tr!("arg-format"))
+                .global(true),
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .help(tr!("arg-color"))
+                .value_parser(["auto", "always", "never"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("jobs")
+                .long("jobs")
+                .value_name("N")
+                .help(tr!("arg-jobs"))
+                .value_parser(clap::value_parser!(usize))
+                .global(true),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .help(tr!("arg-resume"))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("skip-distro-check")
+                .long("skip-distro-check")
+                .help(tr!("arg-skip-distro-check"))
+                .action(ArgAction::SetTrue),
+        )
+        // lists the package names starting with PREFIX, for the completion scripts
+        .arg(
+            Arg::new("complete-aur")
+                .long("complete-aur")
+                .value_name("PREFIX")
+                .hide(true),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about(tr!("cmd-completions"))
+                .arg(
+                    Arg::new("shell")
+                        .help(tr!("arg-shell"))
+                        .value_parser(SHELLS.to_vec())
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("deptree")
+                .about(tr!("cmd-deptree"))
+                .arg(
+                    Arg::new("dot")
+                        .long("dot")
+                        .help(tr!("arg-dot"))
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("package")
+                        .help(tr!("arg-packages"))
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .num_args(1..),
+                ),
+        )
+        .subcommand(
+            Command::new("whyneeded").about(tr!("cmd-whyneeded")).arg(
+                Arg::new("package")
+                    .help(tr!("arg-packages"))
+                    .required(true)
+                    .action(ArgAction::Set)
+                    .num_args(1..),
+            ),
+        )
+        .subcommand(
+            Command::new("query")
+                .short_flag('Q')
+                .long_flag("query")
+                .about(tr!("cmd-query"))
+                .arg(
+                    Arg::new("search")
+                        .short('s')
+                        .long("search")
+                        .help(tr!("arg-query-search"))
+                        .conflicts_with("info")
+                        .action(ArgAction::Set)
+                        .num_args(1..),
+                )
+                // the targets may also come after --required-by, as in `-Qi --required-by foo`
+                .arg(
+                    Arg::new("info")
+                        .short('i')
+                        .long("info")
+                        .help(tr!("arg-query-info"))
+                        .conflicts_with("search")
+                        .action(ArgAction::Set)
+                        .num_args(0..),
+                )
+                .arg(
+                    Arg::new("required-by")
+                        .long("required-by")
+                        .help(tr!("arg-required-by"))
+                        .requires("info")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("package")
+                        .help(tr!("arg-packages"))
+                        .requires("info")
+                        .action(ArgAction::Set)
+                        .num_args(1..),
+                ),
+        )
+        .subcommand(
+            Command::new("sync")
+                .short_flag('S')
+                .long_flag("sync")
+                .about(tr!("cmd-sync"))
+                .arg(
+                    Arg::new("refresh")
+                        .short('y')
+                        .long("refresh")
+                        .help(tr!("arg-refresh"))
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("search")
+                        .short('s')
+                        .long("search")
+                        .help(tr!("arg-sync-search"))
+                        .conflicts_with("info")
+                        .action(ArgAction::Set)
+                        .num_args(1..),
+                )
+                // the targets may also come after --graph, as in `-Si --graph foo`
+                .arg(
+                    Arg::new("info")
+                        .short('i')
+                        .long("info")
+                        .help(tr!("arg-sync-info"))
+                        .conflicts_with("search")
+                        .action(ArgAction::Set)
+                        .num_args(0..),
+                )
+                .arg(
+                    Arg::new("graph")
+                        .long("graph")
+                        .help(tr!("arg-graph"))
+                        .requires("info")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dot")
+                        .long("dot")
+                        .help(tr!("arg-dot"))
+                        .requires("graph")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("by")
+                        .long("by")
+                        .value_name("FIELD")
+                        .help(tr!("arg-by"))
+                        .value_parser([
+                            "name",
+                            "name-desc",
+                            "maintainer",
+                            "depends",
+                            "makedepends",
+                            "keywords",
+                        ])
+                        .default_value("name-desc"),
+                )
+                .arg(
+                    Arg::new("sortby")
+                        .long("sortby")
+                        .value_name("ORDER")
+                        .help(tr!("arg-sortby"))
+                        .value_parser(["votes", "popularity", "name", "modified", "submitted"])
+                        .requires("search"),
+                )
+                .arg(
+                    Arg::new("reverse")
+                        .long("reverse")
+                        .help(tr!("arg-reverse"))
+                        .requires("search")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .help(tr!("arg-limit"))
+                        .value_parser(clap::value_parser!(usize))
+                        .requires("search"),
+                )
+                .arg(
+                    Arg::new("no-outdated")
+                        .long("no-outdated")
+                        .help(tr!("arg-no-outdated"))
+                        .requires("search")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("maintained-only")
+                        .long("maintained-only")
+                        .help(tr!("arg-maintained-only"))
+                        .requires("search")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("min-votes")
+                        .long("min-votes")
+                        .value_name("N")
+                        .help(tr!("arg-min-votes"))
+                        .value_parser(clap::value_parser!(u32))
+                        .requires("search"),
+                )
+                .arg(
+                    Arg::new("needed")
+                        .long("needed")
+                        .help(tr!("arg-needed"))
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("asdeps")
+                        .long("asdeps")
+                        .help(tr!("arg-asdeps"))
+                        .conflicts_with("asexplicit")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("asexplicit")
+                        .long("asexplicit")
+                        .help(tr!("arg-asexplicit"))
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("noconfirm")
+                        .long("noconfirm")
+                        .help(tr!("arg-noconfirm"))
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("print-plan")
+                        .long("print-plan")
+                        .help(tr!("arg-print-plan"))
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("package")
+                        .help(tr!("arg-packages"))
+                        .required_unless_present("search")
+                        .required_unless_present("info")
+                        .action(ArgAction::Set)
+                        .num_args(1..),
+                ),
+        )
+}
+
+/// A short or long flag, without its dashes nor its value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Flag {
+    Short(char),
+    Long(String),
+}
+
+/// A command line split the way pacman reads it, e.g. `-Syu --needed foo`.
+#[derive(Debug, Default)]
+pub struct PacmanArgs {
+    /// The operation's short flag, e.g. `S`.
+    pub op: Option<char>,
+    /// Flags of the operation that rah doesn't know about.
+    pub unknown: Vec<Flag>,
+    /// Flags of the operation that rah knows about.
+    pub known: Vec<Flag>,
+    pub targets: Vec<String>,
+    /// The arguments to give pacman, without rah's own options.
+    pub forwarded: Vec<String>,
+    /// rah's own options given before the operation, such as `--config`.
+    pub own: Vec<String>,
+}
+
+/// Whether an option takes exactly one value, as opposed to flags and to `-s`/`-i` which
+/// take the targets.
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_num_args()
+        .is_some_and(|num_args| num_args.takes_values() && num_args.max_values() == 1)
+}
+
+/// The rah option matching `flag` at that point of the command line : root options before the
+/// operation, the operation's and global ones after it.
+fn find_arg<'a>(cmd: &'a Command, op: Option<char>, flag: &Flag) -> Option<&'a Arg> {
+    let matches = |arg: &&Arg| match flag {
+        Flag::Short(short) => arg.get_short() == Some(*short),
+        Flag::Long(long) => arg.get_long() == Some(long.as_str()),
+    };
+
+    match op {
+        None => cmd.get_arguments().find(matches),
+        Some(op) => cmd
+            .get_subcommands()
+            .find(|sub| sub.get_short_flag() == Some(op))
+            .and_then(|sub| sub.get_arguments().find(matches))
+            .or_else(|| {
+                cmd.get_arguments()
+                    .filter(|arg| arg.is_global_set())
+                    .find(matches)
+            }),
+    }
+}
+
+impl PacmanArgs {
+    pub fn parse(cmd: &Command, args: &[String]) -> Self {
+        // building adds the help and version flags, and propagates global options
+        let mut cmd = cmd.clone();
+        cmd.build();
+        let cmd = &cmd;
+
+        let mut parsed = PacmanArgs::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.forwarded.push(arg.clone());
+                parsed.targets.extend(args.by_ref().cloned());
+                parsed.forwarded.extend(parsed.targets.iter().cloned());
+                break;
+            }
+
+            let flags = if let Some(long) = arg.strip_prefix("--") {
+                let (name, value) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (long, None),
+                };
+                if let Some((op, _)) = OPERATIONS.iter().find(|(_, op)| *op == name) {
+                    parsed.op.get_or_insert(*op);
+                    parsed.forwarded.push(arg.clone());
+                    continue;
+                }
+                vec![(Flag::Long(name.to_string()), value.is_some())]
+            } else if arg.len() > 1 && arg.starts_with('-') {
+                // a cluster such as -Syu, where a value may be glued to the option taking it
+                let mut flags = Vec::new();
+                for (i, short) in arg.char_indices().skip(1) {
+                    if OPERATIONS.iter().any(|(op, _)| *op == short) && parsed.op.is_none() {
+                        parsed.op = Some(short);
+                        continue;
+                    }
+                    let flag = Flag::Short(short);
+                    let glued = i + short.len_utf8() < arg.len();
+                    let takes_value = match find_arg(cmd, parsed.op, &flag) {
+                        Some(rah_arg) => takes_value(rah_arg),
+                        None => PACMAN_VALUE_SHORTS.contains(&short),
+                    };
+                    flags.push((flag, glued && takes_value));
+                    if takes_value {
+                        break;
+                    }
+                }
+                flags
+            } else {
+                parsed.targets.push(arg.clone());
+                parsed.forwarded.push(arg.clone());
+                continue;
+            };
+
+            let mut tokens = vec![arg.clone()];
+            let mut own = false;
+            for (flag, has_value) in flags {
+                let rah_arg = find_arg(cmd, parsed.op, &flag);
+                let takes_value = match (&rah_arg, &flag) {
+                    (Some(rah_arg), _) => takes_value(rah_arg),
+                    (None, Flag::Short(short)) => PACMAN_VALUE_SHORTS.contains(short),
+                    (None, Flag::Long(long)) => PACMAN_VALUE_LONGS.contains(&long.as_str()),
+                };
+                if takes_value && !has_value {
+                    tokens.extend(args.next().cloned());
+                }
+
+                match rah_arg {
+                    Some(rah_arg) if parsed.op.is_none() || rah_arg.is_global_set() => {
+                        own = !SHARED_OPTIONS.contains(&rah_arg.get_id().as_str());
+                    }
+                    Some(_) => parsed.known.push(flag),
+                    None => parsed.unknown.push(flag),
+                }
+            }
+
+            if own {
+                parsed.own.extend(tokens);
+            } else {
+                parsed.forwarded.extend(tokens);
+            }
+        }
+
+        parsed
+    }
+
+    /// Whether the command is one of rah's, rather than one to hand over to pacman.
+    pub fn handled_by_rah(&self) -> bool {
+        let has = |short: char| self.known.contains(&Flag::Short(short));
+
+        match self.op {
+            None => true,
+            Some('S') => {
+                self.unknown.is_empty() && (has('s') || has('i') || !self.targets.is_empty())
+            }
+            Some('Q') => self.unknown.is_empty() && (has('s') || has('i')),
+            Some(_) => false,
+        }
+    }
+
+    /// Whether pacman needs root for the command, so that it can be run through the
+    /// escalation command instead of failing.
+    pub fn needs_root(&self) -> bool {
+        let has = |short: char, long: &str| {
+            self.known
+                .iter()
+                .chain(&self.unknown)
+                .any(|flag| *flag == Flag::Short(short) || *flag == Flag::Long(long.to_string()))
+        };
+
+        match self.op {
+            // searching, listing and printing don't touch the system
+            Some('S') => {
+                !(has('s', "search")
+                    || has('i', "info")
+                    || has('l', "list")
+                    || has('g', "groups")
+                    || has('p', "print"))
+                    || has('y', "refresh")
+            }
+            Some('R') | Some('U') => !has('p', "print"),
+            Some('D') => !has('k', "check"),
+            Some('F') => has('y', "refresh"),
+            _ => false,
+        }
+    }
+}
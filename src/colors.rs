@@ -1,3 +1,15 @@
+use {
+    crate::config::Config,
+    anyhow::{anyhow, Result},
+    std::{
+        collections::HashMap,
+        env, fs,
+        io::{self, IsTerminal},
+        path::Path,
+        sync::OnceLock,
+    },
+};
+
 // const escape sequences
 pub const CLEAR: &str = "\x1b[0m";
 pub const BOLD: &str = "\x1b[1m";
@@ -17,3 +29,221 @@ pub const YELLOW_L: &str = "\x1b[93m";
 //pub const PURPLE_L: &str = "\x1b[95m";
 pub const CYAN_L: &str = "\x1b[96m";
 pub const WHITE: &str = "\x1b[97m";
+
+/// Styles usable in the `[colors]` config table and in templates.
+pub const STYLES: &[(&str, &str)] = &[
+    ("clear", CLEAR),
+    ("bold", BOLD),
+    ("red", RED),
+    ("green", GREEN),
+    ("blue", BLUE),
+    ("cyan", CYAN),
+    ("light_cyan", CYAN_L),
+    ("yellow", YELLOW_L),
+    ("grey", BLACK_L),
+    ("white", WHITE),
+];
+
+/// When to color the output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    /// Only when writing to a terminal, unless `NO_COLOR` is set or pacman.conf doesn't enable
+    /// `Color`.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn parse(mode: &str) -> Result<Self> {
+        match mode {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            other => Err(anyhow!(
+                "Unknown color mode \"{other}\", expected one of auto, always or never"
+            )),
+        }
+    }
+}
+
+/// The escape sequences printed for each part of rah's output, empty when colors are off.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub enabled: bool,
+    pub clear: String,
+    /// Labels, e.g. in `-Si`.
+    pub label: String,
+    /// Placeholders for missing values.
+    pub dim: String,
+    /// The `::` in front of section titles, and the titles themselves.
+    pub header: String,
+    pub title: String,
+    pub name: String,
+    pub version: String,
+    pub installed: String,
+    pub other_version: String,
+    pub out_of_date: String,
+    pub number: String,
+    pub prompt: String,
+    pub ok: String,
+    pub warning: String,
+    pub error: String,
+}
+
+/// Default style of every role, as written in the `[colors]` config table.
+const DEFAULT_ROLES: &[(&str, &str)] = &[
+    ("label", "bold"),
+    ("dim", "grey"),
+    ("header", "bold blue"),
+    ("title", "bold white"),
+    ("name", "bold"),
+    ("version", "bold green"),
+    ("installed", "bold cyan"),
+    ("other_version", "bold light_cyan"),
+    ("out_of_date", "bold red"),
+    ("number", "bold yellow"),
+    ("prompt", "bold green"),
+    ("ok", "green"),
+    ("warning", "bold yellow"),
+    ("error", "bold red"),
+];
+
+/// Escape sequence for a space separated list of `STYLES`.
+pub fn parse_style(style: &str) -> Result<String> {
+    style
+        .split_whitespace()
+        .map(|name| {
+            STYLES
+                .iter()
+                .find(|(style, _)| *style == name)
+                .map(|(_, code)| *code)
+                .ok_or(anyhow!("Unknown color \"{name}\""))
+        })
+        .collect()
+}
+
+/// Whether `name` is a color role or one of the `STYLES`.
+pub fn is_style(name: &str) -> bool {
+    STYLES
+        .iter()
+        .chain(DEFAULT_ROLES)
+        .any(|(style, _)| *style == name)
+}
+
+/// Check the `[colors]` config table, mapping roles to styles.
+pub fn check_roles(roles: &HashMap<String, String>) -> Result<()> {
+    for (role, style) in roles {
+        if !DEFAULT_ROLES.iter().any(|(name, _)| name == role) {
+            return Err(anyhow!("Unknown color role \"{role}\""));
+        }
+        parse_style(style)?;
+    }
+
+    Ok(())
+}
+
+impl Theme {
+    pub fn new(enabled: bool, roles: &HashMap<String, String>) -> Self {
+        let role = |name: &str| {
+            if !enabled {
+                return String::new();
+            }
+            roles
+                .get(name)
+                .and_then(|style| parse_style(style).ok())
+                .or_else(|| {
+                    DEFAULT_ROLES
+                        .iter()
+                        .find(|(role, _)| *role == name)
+                        .and_then(|(_, style)| parse_style(style).ok())
+                })
+                .unwrap_or_default()
+        };
+
+        Theme {
+            enabled,
+            clear: if enabled {
+                CLEAR.to_string()
+            } else {
+                String::new()
+            },
+            label: role("label"),
+            dim: role("dim"),
+            header: role("header"),
+            title: role("title"),
+            name: role("name"),
+            version: role("version"),
+            installed: role("installed"),
+            other_version: role("other_version"),
+            out_of_date: role("out_of_date"),
+            number: role("number"),
+            prompt: role("prompt"),
+            ok: role("ok"),
+            warning: role("warning"),
+            error: role("error"),
+        }
+    }
+
+    /// Escape sequence of a role or of one of the `STYLES`, by name.
+    pub fn style(&self, name: &str) -> Option<String> {
+        if !self.enabled {
+            return is_style(name).then(String::new);
+        }
+
+        let role = match name {
+            "label" => &self.label,
+            "dim" => &self.dim,
+            "header" => &self.header,
+            "title" => &self.title,
+            "name" => &self.name,
+            "version" => &self.version,
+            "installed" => &self.installed,
+            "other_version" => &self.other_version,
+            "out_of_date" => &self.out_of_date,
+            "number" => &self.number,
+            "prompt" => &self.prompt,
+            "ok" => &self.ok,
+            "warning" => &self.warning,
+            "error" => &self.error,
+            other => return parse_style(other).ok(),
+        };
+        Some(role.clone())
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Whether pacman.conf enables colors, assuming it does if it can't be read.
+fn pacman_color(conf: &Config) -> bool {
+    let pacman_conf = Path::new(&conf.pacman_root).join("etc/pacman.conf");
+    let Ok(data) = fs::read_to_string(pacman_conf) else {
+        return true;
+    };
+
+    data.lines().any(|line| line.trim() == "Color")
+}
+
+/// Whether colors should be used with the given mode.
+pub fn enabled(mode: ColorMode, conf: &Config) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty())
+                && io::stdout().is_terminal()
+                && pacman_color(conf)
+        }
+    }
+}
+
+/// Set up the theme according to `conf`, to be called once the config and command line have
+/// been read.
+pub fn init(conf: &Config) {
+    let _ = THEME.set(Theme::new(enabled(conf.color, conf), &conf.colors));
+}
+
+/// The current theme, colored with the default roles if `init` hasn't been called.
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(|| Theme::new(true, &HashMap::new()))
+}
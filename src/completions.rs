@@ -0,0 +1,123 @@
+use {
+    crate::{aur_cache, cli, config::Config, helpers},
+    anyhow::{anyhow, Result},
+    clap_complete::Shell,
+    log::debug,
+    std::io::Write,
+};
+
+/// A word selecting `-S` or `-R`, after which package names get completed.
+const OPERATION_RE: &str = "^(-[^-]*[SR]|--sync$|--remove$)";
+
+/// Wrappers around clap's completion functions, completing package names through
+/// `rah --complete-aur` after `-S` and `-R`.
+fn dynamic_bash() -> String {
+    format!(
+        r#"
+_rah_packages() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" word
+    for word in "${{COMP_WORDS[@]:1:COMP_CWORD-1}}"; do
+        [[ "$word" == -- ]] && break
+        if [[ "$word" =~ {OPERATION_RE} && "$cur" != -* ]]; then
+            COMPREPLY=($(compgen -W "$(rah --complete-aur "$cur" 2>/dev/null)" -- "$cur"))
+            return
+        fi
+    done
+    _rah "$@"
+}}
+
+complete -F _rah_packages -o bashdefault -o default rah
+"#
+    )
+}
+
+fn dynamic_zsh() -> String {
+    format!(
+        r#"
+_rah_packages() {{
+    local word
+    for word in ${{words[2,CURRENT-1]}}; do
+        [[ "$word" == -- ]] && break
+        if [[ "$word" =~ '{OPERATION_RE}' && "$PREFIX" != -* ]]; then
+            compadd -- ${{(f)"$(rah --complete-aur "$PREFIX" 2>/dev/null)"}}
+            return
+        fi
+    done
+    _rah "$@"
+}}
+
+if [ "$funcstack[1]" = "_rah" ]; then
+    _rah_packages "$@"
+else
+    compdef _rah_packages rah
+fi
+"#
+    )
+}
+
+fn dynamic_fish() -> String {
+    format!(
+        r#"
+function __rah_wants_packages
+    for token in (commandline -opc)[2..-1]
+        test "$token" = --; and return 1
+        string match -qr -- '{OPERATION_RE}' $token; and return 0
+    end
+    return 1
+end
+
+complete -c rah -n __rah_wants_packages -f -a '(rah --complete-aur (commandline -ct) 2>/dev/null)'
+"#
+    )
+}
+
+/// Write the completion script for `shell`, one of `cli::SHELLS`.
+pub fn generate(shell: &str, out: &mut dyn Write) -> Result<()> {
+    let (shell, dynamic) = match shell {
+        "bash" => (Shell::Bash, dynamic_bash()),
+        "zsh" => (Shell::Zsh, dynamic_zsh()),
+        "fish" => (Shell::Fish, dynamic_fish()),
+        other => return Err(anyhow!("Unsupported shell \"{other}\"")),
+    };
+
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut cli::command(), "rah", &mut script);
+    let mut script = String::from_utf8(script)?;
+
+    // zsh autoloads the file as the _rah function, which ends by dispatching to the
+    // completion function : have it pick ours instead
+    if shell == Shell::Zsh {
+        if let Some(dispatch) = script.rfind("if [ \"$funcstack[1]\" = \"_rah\" ]") {
+            script.truncate(dispatch);
+        }
+    }
+
+    out.write_all(script.as_bytes())?;
+    out.write_all(dynamic.as_bytes())?;
+
+    Ok(())
+}
+
+/// Package names starting with `prefix` : AUR packages from the metadata cache, and installed
+/// foreign packages. Never fails nor touches the network, as it runs on every tab press.
+pub fn complete_packages(conf: &Config, prefix: &str) -> Vec<String> {
+    let mut names = aur_cache::names(conf)
+        .inspect_err(|err| debug!("No AUR names to complete : {err}"))
+        .unwrap_or_default();
+
+    match helpers::alpm(conf) {
+        Ok(alpm) => names.extend(
+            alpm.localdb()
+                .pkgs()
+                .iter()
+                .filter(|pkg| alpm.syncdbs().iter().all(|db| db.pkg(pkg.name()).is_err()))
+                .map(|pkg| pkg.name().to_string()),
+        ),
+        Err(err) => debug!("No installed packages to complete : {err}"),
+    }
+
+    names.retain(|name| name.starts_with(prefix));
+    names.sort();
+    names.dedup();
+    names
+}
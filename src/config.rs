@@ -1,27 +1,261 @@
-use {anyhow::Result, toml};
+use {
+    crate::{
+        colors::{self, ColorMode},
+        config_schema::KEYS,
+        output::{OutputFormat, KINDS},
+        template::Template,
+    },
+    anyhow::{anyhow, Context, Result},
+    log::{info, warn},
+    std::{collections::HashMap, env, path::Path},
+    toml::{Table, Value},
+    users::get_current_uid,
+};
+
+pub const AUR_URL: &str = "https://aur.archlinux.org/";
 
 pub struct Config {
     pub config_path: String,
     pub db_path: String,
     pub cache_path: String,
-    pub color: bool,
+    pub color: ColorMode,
+    pub colors: HashMap<String, String>,
     pub delete_make_deps: Option<bool>,
+    pub local_repo: bool,
+    pub local_repo_name: String,
+    pub aur_cache: bool,
+    pub aur_cache_max_age: u64,
+    pub offline: bool,
+    pub aur_concurrency: usize,
+    /// How many AUR packages get built at the same time.
+    pub build_jobs: usize,
+    pub aur_url: String,
+    pub pacman_root: String,
+    pub pacman_db_path: String,
+    pub repos: Vec<String>,
+    pub escalation_cmd: String,
+    /// Extra flags given to pacman when installing packages, e.g. `--noconfirm`.
+    pub pacman_flags: Vec<String>,
+    pub build_user: Option<String>,
+    pub pgp_keyserver: Option<String>,
+    /// Keyring file looked up for the PGP keys of PKGBUILDs before the keyservers.
+    pub pgp_keyring: Option<String>,
+    pub skip_distro_check: bool,
+    pub output_format: OutputFormat,
+    pub templates: HashMap<String, Template>,
     //pub pager_cmd: Option<String>,
 }
 
 impl Config {
     pub fn default() -> Self {
+        let (db_path, cache_path) = if get_current_uid() == 0 {
+            (
+                String::from("/var/lib/rah/db/"),
+                String::from("/var/cache/rah/"),
+            )
+        } else {
+            user_paths()
+        };
+
         Config {
             config_path: String::from("/etc/rah.toml"),
-            db_path: String::from("/var/lib/rah/db/"),
-            cache_path: String::from("/var/cache/rah/"),
-            color: true,
+            db_path,
+            cache_path,
+            color: ColorMode::Auto,
+            colors: HashMap::new(),
             delete_make_deps: None,
+            local_repo: false,
+            local_repo_name: String::from("rah-local"),
+            aur_cache: true,
+            aur_cache_max_age: 24,
+            offline: false,
+            aur_concurrency: 8,
+            build_jobs: 1,
+            aur_url: String::from(AUR_URL),
+            pacman_root: String::from("/"),
+            pacman_db_path: String::from("/var/lib/pacman/"),
+            repos: vec![
+                String::from("core"),
+                String::from("extra"),
+                String::from("multilib"),
+            ],
+            escalation_cmd: String::from("sudo"),
+            pacman_flags: Vec::new(),
+            build_user: None,
+            pgp_keyserver: None,
+            pgp_keyring: None,
+            skip_distro_check: false,
+            output_format: OutputFormat::Text,
+            templates: HashMap::new(),
             //pager_cmd: Some(String::from("less -r")),
         }
     }
 
     pub fn parse(&mut self, cfg_path: Option<String>) -> Result<()> {
-        todo!("{:?}", cfg_path)
+        if let Some(cfg_path) = cfg_path {
+            self.config_path = cfg_path;
+        }
+
+        // mostly meant for pointing rah at a mirror or a mock AUR when testing
+        if let Ok(aur_url) = env::var("RAH_AUR_URL") {
+            self.aur_url = aur_url;
+        }
+
+        if !Path::new(&self.config_path).exists() {
+            info!(
+                "No config file found at \"{}\", using defaults",
+                self.config_path
+            );
+            return Ok(());
+        }
+
+        let data = std::fs::read_to_string(&self.config_path)
+            .with_context(|| format!("Could not read config file \"{}\"", self.config_path))?;
+        let table = data
+            .parse::<Table>()
+            .with_context(|| format!("Invalid config file \"{}\"", self.config_path))?;
+
+        for (key, value) in table.iter() {
+            if !KEYS.iter().any(|known| known.name == key) {
+                warn!("Unknown config key \"{key}\", ignoring it");
+                continue;
+            }
+
+            match key.as_str() {
+                "db_path" => self.db_path = as_string(key, value)?,
+                "cache_path" => self.cache_path = as_string(key, value)?,
+                "color" => self.color = as_color_mode(key, value)?,
+                "colors" => {
+                    self.colors = as_string_table(key, value)?;
+                    colors::check_roles(&self.colors)?;
+                }
+                "delete_make_deps" => self.delete_make_deps = Some(as_bool(key, value)?),
+                "local_repo" => self.local_repo = as_bool(key, value)?,
+                "local_repo_name" => self.local_repo_name = as_string(key, value)?,
+                "aur_cache" => self.aur_cache = as_bool(key, value)?,
+                "aur_cache_max_age" => self.aur_cache_max_age = as_uint(key, value)?,
+                "offline" => self.offline = as_bool(key, value)?,
+                "aur_concurrency" => self.aur_concurrency = as_uint(key, value)? as usize,
+                "build_jobs" => self.build_jobs = as_uint(key, value)? as usize,
+                "pacman_root" => self.pacman_root = as_string(key, value)?,
+                "pacman_db_path" => self.pacman_db_path = as_string(key, value)?,
+                "repos" => self.repos = as_string_list(key, value)?,
+                "escalation_cmd" => self.escalation_cmd = as_string(key, value)?,
+                "pacman_flags" => self.pacman_flags = as_string_list(key, value)?,
+                "build_user" => self.build_user = Some(as_string(key, value)?),
+                "pgp_keyserver" => self.pgp_keyserver = Some(as_string(key, value)?),
+                "pgp_keyring" => self.pgp_keyring = Some(as_string(key, value)?),
+                "skip_distro_check" => self.skip_distro_check = as_bool(key, value)?,
+                "output_format" => {
+                    self.output_format = OutputFormat::parse(&as_string(key, value)?)?
+                }
+                "templates" => self.templates = as_templates(key, value)?,
+                "aur_url" => {
+                    let aur_url = as_string(key, value)?;
+                    if env::var("RAH_AUR_URL").is_err() {
+                        self.aur_url = aur_url;
+                    }
+                }
+                // tests/config.rs makes sure every documented key is handled
+                _ => {
+                    debug_assert!(false, "config key \"{key}\" is documented but not handled");
+                    warn!("Config key \"{key}\" isn't supported yet, ignoring it");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Full URL of `path` on the configured AUR instance.
+    pub fn aur_endpoint(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.aur_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
     }
 }
+
+/// Database and cache directories of a regular user, following the XDG base directories.
+fn user_paths() -> (String, String) {
+    let home = env::var("HOME").unwrap_or_default();
+    let data_home = env::var("XDG_DATA_HOME").unwrap_or_else(|_| format!("{home}/.local/share"));
+    let cache_home = env::var("XDG_CACHE_HOME").unwrap_or_else(|_| format!("{home}/.cache"));
+
+    (format!("{data_home}/rah/db/"), format!("{cache_home}/rah/"))
+}
+
+fn as_string(key: &str, value: &Value) -> Result<String> {
+    value
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or(anyhow!("Config key \"{key}\" should be a string"))
+}
+
+fn as_string_list(key: &str, value: &Value) -> Result<Vec<String>> {
+    value
+        .as_array()
+        .and_then(|array| {
+            array
+                .iter()
+                .map(|item| item.as_str().map(|s| s.to_string()))
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or(anyhow!("Config key \"{key}\" should be a list of strings"))
+}
+
+/// A `[templates]` table, mapping kinds of records to their output template.
+fn as_templates(key: &str, value: &Value) -> Result<HashMap<String, Template>> {
+    let table = value
+        .as_table()
+        .ok_or(anyhow!("Config key \"{key}\" should be a table"))?;
+
+    table
+        .iter()
+        .map(|(kind, template)| {
+            if !KINDS.contains(&kind.as_str()) {
+                return Err(anyhow!(
+                    "Unknown kind \"{kind}\" in \"{key}\", expected one of {}",
+                    KINDS.join(", ")
+                ));
+            }
+            let template = Template::parse(&as_string(&format!("{key}.{kind}"), template)?)?;
+            Ok((kind.clone(), template))
+        })
+        .collect()
+}
+
+/// `color` used to be a boolean, `true` now meaning "auto".
+fn as_color_mode(key: &str, value: &Value) -> Result<ColorMode> {
+    match value {
+        Value::Boolean(true) => Ok(ColorMode::Auto),
+        Value::Boolean(false) => Ok(ColorMode::Never),
+        Value::String(mode) => ColorMode::parse(mode),
+        _ => Err(anyhow!(
+            "Config key \"{key}\" should be one of \"auto\", \"always\" or \"never\""
+        )),
+    }
+}
+
+fn as_string_table(key: &str, value: &Value) -> Result<HashMap<String, String>> {
+    value
+        .as_table()
+        .ok_or(anyhow!("Config key \"{key}\" should be a table"))?
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), as_string(&format!("{key}.{name}"), value)?)))
+        .collect()
+}
+
+fn as_bool(key: &str, value: &Value) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or(anyhow!("Config key \"{key}\" should be a boolean"))
+}
+
+fn as_uint(key: &str, value: &Value) -> Result<u64> {
+    value
+        .as_integer()
+        .and_then(|i| u64::try_from(i).ok())
+        .ok_or(anyhow!("Config key \"{key}\" should be a positive integer"))
+}
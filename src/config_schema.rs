@@ -0,0 +1,158 @@
+/// A key of the config file, as documented in rah.toml(5).
+pub struct ConfigKey {
+    pub name: &'static str,
+    /// Type of the value, as shown to users.
+    pub kind: &'static str,
+    /// Default value written as TOML, `None` when it depends on the environment.
+    pub default: Option<&'static str>,
+    pub description: &'static str,
+}
+
+/// Every key of the config file. This is also what builds rah.toml(5), keys missing from here
+/// are ignored as unknown.
+pub const KEYS: &[ConfigKey] = &[
+    ConfigKey {
+        name: "db_path",
+        kind: "string",
+        default: None,
+        description: "Directory of rah's package database. Defaults to $XDG_DATA_HOME/rah/db/, or /var/lib/rah/db/ when running as root.",
+    },
+    ConfigKey {
+        name: "cache_path",
+        kind: "string",
+        default: None,
+        description: "Directory where packages are cloned and built, and where the AUR metadata is cached. Defaults to $XDG_CACHE_HOME/rah/, or /var/cache/rah/ when running as root.",
+    },
+    ConfigKey {
+        name: "color",
+        kind: "string",
+        default: Some("\"auto\""),
+        description: "When to color the output : \"auto\" (on a terminal, unless NO_COLOR is set or pacman.conf doesn't enable Color), \"always\" or \"never\".",
+    },
+    ConfigKey {
+        name: "colors",
+        kind: "table",
+        default: Some("{}"),
+        description: "Styles of each part of the output, e.g. name = \"bold\" or version = \"bold green\". Styles are space separated among bold, red, green, blue, cyan, light_cyan, yellow, grey and white.",
+    },
+    ConfigKey {
+        name: "delete_make_deps",
+        kind: "boolean",
+        default: None,
+        description: "Whether to remove the dependencies only needed to build packages once they are installed. rah asks when unset.",
+    },
+    ConfigKey {
+        name: "local_repo",
+        kind: "boolean",
+        default: Some("false"),
+        description: "Store built packages in a local pacman repository.",
+    },
+    ConfigKey {
+        name: "local_repo_name",
+        kind: "string",
+        default: Some("\"rah-local\""),
+        description: "Name of the local repository.",
+    },
+    ConfigKey {
+        name: "aur_cache",
+        kind: "boolean",
+        default: Some("true"),
+        description: "Search and look packages up in a local copy of the AUR metadata rather than through the RPC.",
+    },
+    ConfigKey {
+        name: "aur_cache_max_age",
+        kind: "integer",
+        default: Some("24"),
+        description: "Hours after which the local copy of the AUR metadata gets downloaded again.",
+    },
+    ConfigKey {
+        name: "offline",
+        kind: "boolean",
+        default: Some("false"),
+        description: "Only use the local copy of the AUR metadata, never reaching the network.",
+    },
+    ConfigKey {
+        name: "aur_concurrency",
+        kind: "integer",
+        default: Some("8"),
+        description: "Maximum number of concurrent requests to the AUR RPC.",
+    },
+    ConfigKey {
+        name: "build_jobs",
+        kind: "integer",
+        default: Some("1"),
+        description: "Maximum number of AUR packages built at the same time, among the ones that don't depend on each other. Overridden by --jobs.",
+    },
+    ConfigKey {
+        name: "aur_url",
+        kind: "string",
+        default: Some("\"https://aur.archlinux.org/\""),
+        description: "AUR instance to use, overridden by the RAH_AUR_URL environment variable.",
+    },
+    ConfigKey {
+        name: "pacman_root",
+        kind: "string",
+        default: Some("\"/\""),
+        description: "Root directory of the system pacman manages.",
+    },
+    ConfigKey {
+        name: "pacman_db_path",
+        kind: "string",
+        default: Some("\"/var/lib/pacman/\""),
+        description: "Directory of pacman's databases.",
+    },
+    ConfigKey {
+        name: "repos",
+        kind: "list of strings",
+        default: Some("[\"core\", \"extra\", \"multilib\"]"),
+        description: "Sync databases to look packages up in before the AUR.",
+    },
+    ConfigKey {
+        name: "escalation_cmd",
+        kind: "string",
+        default: Some("\"sudo\""),
+        description: "Command running pacman as root, e.g. \"doas\", \"run0\" or \"pkexec\".",
+    },
+    ConfigKey {
+        name: "pacman_flags",
+        kind: "list of strings",
+        default: Some("[]"),
+        description: "Extra flags given to pacman when installing packages, e.g. [\"--noconfirm\"].",
+    },
+    ConfigKey {
+        name: "build_user",
+        kind: "string",
+        default: None,
+        description: "User building packages when rah runs as root, created as a system user if it doesn't exist. rah refuses to build as root when unset.",
+    },
+    ConfigKey {
+        name: "pgp_keyserver",
+        kind: "string",
+        default: None,
+        description: "Keyserver the PGP keys of PKGBUILDs (validpgpkeys) are searched and imported from, gpg's own when unset.",
+    },
+    ConfigKey {
+        name: "pgp_keyring",
+        kind: "string",
+        default: None,
+        description: "Local keyring file the PGP keys of PKGBUILDs are looked up and imported from before the keyserver, e.g. a keyring shared by several machines.",
+    },
+    ConfigKey {
+        name: "skip_distro_check",
+        kind: "boolean",
+        default: Some("false"),
+        description: "Run even if the system doesn't look like an Arch-based one.",
+    },
+    ConfigKey {
+        name: "output_format",
+        kind: "string",
+        default: Some("\"text\""),
+        description: "Default output format : \"text\", \"json\", \"ndjson\" or a template.",
+    },
+    ConfigKey {
+        name: "templates",
+        kind: "table",
+        default: Some("{}"),
+        description: "Templates replacing the text output of aur_search, aur_info, local_search, local_info, plan and reverse_deps.",
+    },
+];
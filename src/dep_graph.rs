@@ -0,0 +1,172 @@
+use {
+    crate::{
+        colors::{self, Theme},
+        dep_tree::{DepTree, DepType, Pkg},
+        tr,
+    },
+    std::{collections::HashSet, fmt::Write},
+};
+
+/// Print `trees` indented, each package marked with where it comes from, why it is needed
+/// and whether it is already installed.
+pub fn print_tree(trees: &[DepTree]) {
+    for tree in trees {
+        print_node(tree, "", None);
+    }
+}
+
+/// Label of a dependency type, none for targets.
+fn reason(dep_type: DepType) -> Option<String> {
+    match dep_type {
+        DepType::Base => None,
+        dep_type => Some(tr!(&format!("reason-{}", dep_type.as_str()))),
+    }
+}
+
+/// Print `tree` under `prefix`, `last` telling whether it is its parent's last leave (`None`
+/// for roots).
+fn print_node(tree: &DepTree, prefix: &str, last: Option<bool>) {
+    let Theme {
+        name,
+        version,
+        installed,
+        dim,
+        clear,
+        ..
+    } = colors::theme();
+
+    let (branch, child_prefix) = match last {
+        None => ("", String::new()),
+        Some(false) => ("├── ", format!("{prefix}│   ")),
+        Some(true) => ("└── ", format!("{prefix}    ")),
+    };
+
+    let pkg = tree.pkg();
+    let source = match pkg {
+        Pkg::Pacman { .. } => tr!("graph-repo"),
+        Pkg::Aur { .. } => tr!("graph-aur"),
+    };
+    let mut line = format!(
+        "{prefix}{branch}{name}{}{clear} {version}{}{clear} {dim}[{source}]{clear}",
+        pkg.name(),
+        pkg.version()
+    );
+    if let Some(reason) = reason(tree.dep_type()) {
+        let _ = write!(line, " {dim}({reason}){clear}");
+    }
+    if tree.installed() {
+        let _ = write!(line, " {installed}[{}]{clear}", tr!("graph-installed"));
+    } else if tree.leaves().is_none() && matches!(pkg, Pkg::Aur { .. }) {
+        let _ = write!(line, " {dim}({}){clear}", tr!("graph-seen"));
+    }
+    println!("{line}");
+
+    let leaves = tree.leaves().map(|leaves| leaves.as_slice()).unwrap_or(&[]);
+    let count = leaves.len() + tree.missing().len();
+    for (i, leave) in leaves.iter().enumerate() {
+        print_node(leave, &child_prefix, Some(i + 1 == count));
+    }
+    for (i, (dep, dep_type)) in tree.missing().iter().enumerate() {
+        print_missing(dep, *dep_type, &child_prefix, leaves.len() + i + 1 == count);
+    }
+}
+
+fn print_missing(dep: &str, dep_type: DepType, prefix: &str, last: bool) {
+    let Theme {
+        error, dim, clear, ..
+    } = colors::theme();
+
+    let branch = if last { "└── " } else { "├── " };
+    let reason = reason(dep_type)
+        .map(|reason| format!(" {dim}({reason}){clear}"))
+        .unwrap_or_default();
+    println!(
+        "{prefix}{branch}{error}{dep}{clear}{reason} {error}[{}]{clear}",
+        tr!("graph-missing")
+    );
+}
+
+/// `trees` as a Graphviz digraph : AUR packages are boxes, installed packages are grey,
+/// dependencies nothing satisfies are red, and build-only dependencies are dashed edges.
+pub fn dot(trees: &[DepTree]) -> String {
+    let mut out =
+        String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=ellipse];\n");
+    let mut nodes = HashSet::new();
+    let mut edges = HashSet::new();
+    for tree in trees {
+        dot_node(tree, &mut out, &mut nodes, &mut edges);
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn dot_node(
+    tree: &DepTree,
+    out: &mut String,
+    nodes: &mut HashSet<String>,
+    edges: &mut HashSet<(String, String)>,
+) {
+    let pkg = tree.pkg();
+    let name = pkg.name();
+
+    if nodes.insert(name.clone()) {
+        let mut attrs = vec![format!(
+            "label=\"{}\\n{}\"",
+            escape(&name),
+            escape(&pkg.version())
+        )];
+        if matches!(pkg, Pkg::Aur { .. }) {
+            attrs.push(String::from("shape=box"));
+        }
+        if tree.installed() {
+            attrs.push(String::from("color=grey fontcolor=grey"));
+        }
+        if tree.dep_type() == DepType::Base {
+            attrs.push(String::from("penwidth=2"));
+        }
+        let _ = writeln!(out, "    \"{}\" [{}];", escape(&name), attrs.join(" "));
+    }
+
+    for leave in tree.leaves().into_iter().flatten() {
+        dot_edge(out, edges, &name, &leave.pkg().name(), leave.dep_type());
+        dot_node(leave, out, nodes, edges);
+    }
+    for (dep, dep_type) in tree.missing() {
+        if nodes.insert(dep.clone()) {
+            let _ = writeln!(
+                out,
+                "    \"{}\" [shape=octagon color=red fontcolor=red];",
+                escape(dep)
+            );
+        }
+        dot_edge(out, edges, &name, dep, *dep_type);
+    }
+}
+
+fn dot_edge(
+    out: &mut String,
+    edges: &mut HashSet<(String, String)>,
+    from: &str,
+    to: &str,
+    dep_type: DepType,
+) {
+    if !edges.insert((from.to_string(), to.to_string())) {
+        return;
+    }
+    let style = if dep_type.is_build_only() {
+        " style=dashed"
+    } else {
+        ""
+    };
+    let _ = writeln!(
+        out,
+        "    \"{}\" -> \"{}\" [label=\"{}\"{style}];",
+        escape(from),
+        escape(to),
+        dep_type.as_str()
+    );
+}
+
+fn escape(id: &str) -> String {
+    id.replace('\\', "\\\\").replace('"', "\\\"")
+}
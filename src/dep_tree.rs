@@ -1,12 +1,14 @@
 use {
-    crate::colors::*,
-    alpm::{Alpm, AlpmList, Db, SigLevel},
+    crate::aur_scheduler::AurScheduler,
+    alpm::Alpm,
     anyhow::{anyhow, Result},
-    raur::{self, Raur},
+    log::trace,
+    serde::{Deserialize, Serialize},
+    std::{cmp::Ordering, collections::HashSet},
 };
 
-#[derive(Clone)]
-enum DepType {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepType {
     Base,
     Dep,
     MakeDep,
@@ -14,6 +16,24 @@ enum DepType {
     CheckDep,
 }
 
+impl DepType {
+    /// Name of the dependency type, as found in records.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DepType::Base => "target",
+            DepType::Dep => "depends",
+            DepType::MakeDep => "make_depends",
+            DepType::OptDep => "opt_depends",
+            DepType::CheckDep => "check_depends",
+        }
+    }
+
+    /// Whether the package is only needed to build others, and can go once they are.
+    pub fn is_build_only(&self) -> bool {
+        matches!(self, DepType::MakeDep | DepType::CheckDep)
+    }
+}
+
 #[derive(Clone)]
 pub enum Pkg<'a> {
     Pacman { pkg: &'a alpm::Package },
@@ -39,6 +59,34 @@ impl Pkg<'_> {
         }
     }
 
+    pub fn version(&self) -> String {
+        match self {
+            Pkg::Pacman { pkg } => pkg.version().to_string(),
+            Pkg::Aur { pkg } => pkg.version.clone(),
+        }
+    }
+
+    pub fn provides(&self) -> Vec<String> {
+        match self {
+            Pkg::Pacman { pkg } => pkg.provides().iter().map(|dep| dep.to_string()).collect(),
+            Pkg::Aur { pkg } => pkg.provides.clone(),
+        }
+    }
+
+    pub fn conflicts(&self) -> Vec<String> {
+        match self {
+            Pkg::Pacman { pkg } => pkg.conflicts().iter().map(|dep| dep.to_string()).collect(),
+            Pkg::Aur { pkg } => pkg.conflicts.clone(),
+        }
+    }
+
+    pub fn replaces(&self) -> Vec<String> {
+        match self {
+            Pkg::Pacman { pkg } => pkg.replaces().iter().map(|dep| dep.to_string()).collect(),
+            Pkg::Aur { pkg } => pkg.replaces.clone(),
+        }
+    }
+
     pub fn make_depends(&self) -> Result<Vec<String>> {
         match self {
             Pkg::Pacman { .. } => Err(anyhow!("Alpm packages don't need make dependencies")),
@@ -63,139 +111,319 @@ impl<'a> From<&'a alpm::Package> for Pkg<'a> {
 pub struct DepTree<'a> {
     pkg: Pkg<'a>,
     dep_type: DepType,
+    installed: bool,
     leaves: Option<Vec<DepTree<'a>>>,
-    parent: Option<*mut DepTree<'a>>,
+    missing: Vec<(String, DepType)>,
+}
+
+/// Flat version of a tree node used while resolving, leaves being indices in the arena.
+struct Node<'a> {
+    pkg: Pkg<'a>,
+    dep_type: DepType,
+    installed: bool,
+    leaves: Option<Vec<usize>>,
+    missing: Vec<(String, DepType)>,
 }
 
 impl<'a> DepTree<'a> {
+    pub fn pkg(&self) -> &Pkg<'a> {
+        &self.pkg
+    }
+
+    pub fn dep_type(&self) -> DepType {
+        self.dep_type
+    }
+
+    /// Whether the package is already installed.
+    pub fn installed(&self) -> bool {
+        self.installed
+    }
+
+    /// Dependencies of the package, `None` if they aren't ours to resolve (repo or installed
+    /// packages) or if they were already resolved elsewhere in the tree.
+    pub fn leaves(&self) -> Option<&Vec<DepTree<'a>>> {
+        self.leaves.as_ref()
+    }
+
+    /// Dependencies of the package that neither the repos nor the AUR satisfy.
+    pub fn missing(&self) -> &[(String, DepType)] {
+        &self.missing
+    }
+
+    /// Resolve the dependency trees of `packages`, failing if any dependency can't be
+    /// satisfied.
     pub async fn build_all(
-        packages: &'a Vec<Pkg<'_>>,
+        packages: &[Pkg<'a>],
         alpm: &'a Alpm,
-        raur: &raur::Handle,
+        scheduler: &mut AurScheduler<'_>,
     ) -> Result<Vec<DepTree<'a>>> {
-        let mut res: Vec<DepTree> = Vec::new();
-
-        // TODO: I think there's a better way to know which repos are used with pacman-conf -l (or
-        // with alpm but the config options don't seem to be implemented in the rust interface)
-        // TODO: Lookup why I still have to register those dbs despite them being already retrieved
-        if alpm.syncdbs().is_empty() {
-            match alpm.register_syncdb("core", SigLevel::NONE) {
-                Err(err) => eprintln!(
-                    "{YELLOW_L}{BOLD}warning :{CLEAR} Could not register db : {}",
-                    err
-                ),
-                Ok(_) => (),
-            };
-            match alpm.register_syncdb("extra", SigLevel::NONE) {
-                Err(err) => eprintln!(
-                    "{YELLOW_L}{BOLD}warning :{CLEAR} Could not register db : {}",
-                    err
-                ),
-                Ok(_) => (),
-            };
-            alpm.register_syncdb("multilib", SigLevel::NONE)?;
-            match alpm.register_syncdb("extra", SigLevel::NONE) {
-                Err(err) => eprintln!(
-                    "{YELLOW_L}{BOLD}warning :{CLEAR} Could not register db : {}",
-                    err
-                ),
-                Ok(_) => (),
-            };
-        }
-
-        let local_db = alpm.localdb();
-        let sync_dbs = alpm.syncdbs();
+        let trees = DepTree::resolve_all(packages, alpm, scheduler).await?;
 
-        for package in packages {
-            res.push(DepTree::build(package, &local_db, &sync_dbs, &raur, None).await?);
+        let mut not_found = Vec::new();
+        for tree in &trees {
+            tree.collect_missing(&mut not_found);
+        }
+        if !not_found.is_empty() {
+            not_found.sort();
+            not_found.dedup();
+            return Err(anyhow!(
+                "No match found for dependencies {}",
+                not_found.join(", ")
+            ));
         }
-        Ok(res)
+
+        Ok(trees)
     }
 
-    pub async fn build(
-        package: &'a Pkg<'_>,
-        local_db: &Db,
-        sync_dbs: &AlpmList<'_, &'a Db>,
-        raur: &raur::Handle,
-        prev: Option<&DepTree<'_>>,
-    ) -> Result<Self> {
-        let mut branch = Self {
-            pkg: package.clone(),
-            dep_type: DepType::Base,
-            leaves: Some(Vec::new()),
-            parent: None,
-        };
+    fn collect_missing(&self, not_found: &mut Vec<String>) {
+        not_found.extend(self.missing.iter().map(|(dep, _)| dep.clone()));
+        for leave in self.leaves.iter().flatten() {
+            leave.collect_missing(not_found);
+        }
+    }
 
-        let local_pkgs = local_db.pkgs();
+    /// Resolve the dependency trees of `packages`, one tree level at a time so that every AUR
+    /// lookup of a level can be batched by the scheduler. Dependencies nothing satisfies are
+    /// kept in `missing()` rather than failing.
+    pub async fn resolve_all(
+        packages: &[Pkg<'a>],
+        alpm: &'a Alpm,
+        scheduler: &mut AurScheduler<'_>,
+    ) -> Result<Vec<DepTree<'a>>> {
+        let local_pkgs = alpm.localdb().pkgs();
+        let sync_dbs = alpm.syncdbs();
 
-        let mut leaves: Vec<DepTree> = Vec::new();
-        let mut not_found: Vec<String> = Vec::new(); // TODO: fill the Vec. Now. Do it. Just
-                                                     // reminding you. No pressure.
+        let mut nodes = packages
+            .iter()
+            .map(|pkg| Node {
+                pkg: pkg.clone(),
+                dep_type: DepType::Base,
+                installed: false,
+                leaves: Some(Vec::new()),
+                missing: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let mut expanded = packages
+            .iter()
+            .map(|pkg| pkg.name())
+            .collect::<HashSet<_>>();
+        let mut level = (0..nodes.len()).collect::<Vec<_>>();
 
-        //let mut cache = HashSet::new(); // because caching could be a nice thing, I just don't
-        //know how much I'd actually benefit from it
+        while !level.is_empty() {
+            let mut wanted = Vec::new();
+            for &i in &level {
+                if let Pkg::Aur { pkg } = &nodes[i].pkg {
+                    for (deps, dep_type) in [
+                        (&pkg.depends, DepType::Dep),
+                        (&pkg.make_depends, DepType::MakeDep),
+                        (&pkg.check_depends, DepType::CheckDep),
+                    ] {
+                        wanted.extend(deps.iter().map(|dep| (i, dep.clone(), dep_type)));
+                    }
+                }
+            }
 
-        for pkg in package.depends() {
-            println!("checking sat for {pkg}");
-            if let Some(_) = local_pkgs.find_satisfier(pkg.clone()) {
-                println!("sat");
-                continue; // package already installed with a correct version
+            // repo packages first, the AUR being only a fallback
+            let mut unresolved = Vec::new();
+            for (parent, dep, dep_type) in wanted {
+                let (pkg, installed) = match local_pkgs.find_satisfier(dep.as_str()) {
+                    Some(pkg) => (pkg, true),
+                    None => match sync_dbs
+                        .iter()
+                        .find_map(|db| db.pkgs().find_satisfier(dep.as_str()))
+                    {
+                        Some(pkg) => (pkg, false),
+                        None => {
+                            unresolved.push((parent, dep, dep_type));
+                            continue;
+                        }
+                    },
+                };
+                trace!("{dep} satisfied by repo package {}", pkg.name());
+                let leave = push_node(&mut nodes, parent, pkg.into(), dep_type, installed);
+                nodes[leave].leaves = None;
             }
 
-            for db in *sync_dbs {
-                match db.pkgs().find_satisfier(pkg.clone()) {
-                    Some(pkg) => {
-                        // a pacman pkg
-                        println!("pacman unsat");
-                        let leave = DepTree {
-                            pkg: pkg.into(),
-                            dep_type: DepType::Dep,
-                            leaves: None,
-                            parent: Some(&mut branch as *mut DepTree<'a>), // further be dragons !
-                        };
-                        leaves.push(leave);
-                        break;
-                    }
-                    None => (),
+            let unresolved_deps = unresolved
+                .iter()
+                .map(|(_, dep, _)| dep.clone())
+                .collect::<Vec<_>>();
+            scheduler.resolve(&unresolved_deps).await?;
+
+            let mut next_level = Vec::new();
+            for (parent, dep, dep_type) in unresolved {
+                let Some(pkg) = scheduler.get(&dep) else {
+                    nodes[parent].missing.push((dep, dep_type));
+                    continue;
                 };
+                trace!("{dep} satisfied by AUR package {}", pkg.name);
+                let first_seen = expanded.insert(pkg.name.clone());
+                let leave = push_node(&mut nodes, parent, pkg.clone().into(), dep_type, false);
+                if first_seen {
+                    next_level.push(leave);
+                } else {
+                    nodes[leave].leaves = None;
+                }
             }
 
-            // an aur pkg, but how can I do dependency lookup not horribly,
-            // except by caching raur's results ?
-            println!("aur unsat");
-            let (pkg_name, pkg_ver_req) = parse_dependency(&pkg);
-            let res = raur.search_by(pkg_name, raur::SearchBy::Provides).await?;
-            println!("is provided by {res:?}");
-            if res.is_empty() {
-                return Err(anyhow!(format!("No match found for dependency {}", pkg)));
+            level = next_level;
+        }
+
+        let mut nodes = nodes.into_iter().map(Some).collect::<Vec<_>>();
+        Ok((0..packages.len())
+            .map(|i| DepTree::from_arena(&mut nodes, i))
+            .collect())
+    }
+
+    fn from_arena(nodes: &mut Vec<Option<Node<'a>>>, i: usize) -> Self {
+        let node = nodes[i]
+            .take()
+            .expect("dependency tree nodes have a single parent");
+        DepTree {
+            pkg: node.pkg,
+            dep_type: node.dep_type,
+            installed: node.installed,
+            missing: node.missing,
+            leaves: node.leaves.map(|leaves| {
+                leaves
+                    .into_iter()
+                    .map(|leave| DepTree::from_arena(nodes, leave))
+                    .collect()
+            }),
+        }
+    }
+}
+
+fn push_node<'a>(
+    nodes: &mut Vec<Node<'a>>,
+    parent: usize,
+    pkg: Pkg<'a>,
+    dep_type: DepType,
+    installed: bool,
+) -> usize {
+    nodes.push(Node {
+        pkg,
+        dep_type,
+        installed,
+        leaves: Some(Vec::new()),
+        missing: Vec::new(),
+    });
+    let i = nodes.len() - 1;
+    if let Some(leaves) = nodes[parent].leaves.as_mut() {
+        leaves.push(i);
+    }
+    i
+}
+
+/// Two packages that can't be installed together.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Conflict {
+    /// The package about to be installed.
+    pub package: String,
+    pub conflicts_with: String,
+    /// Whether `conflicts_with` is installed, rather than about to be installed too.
+    pub installed: bool,
+}
+
+/// Conflicts of `pkgs`, about to be installed, with installed packages and with each other.
+/// Conflicts are checked both ways and through provides, packages only conflicting with the
+/// installed version of themselves being upgrades.
+pub fn find_conflicts(pkgs: &[Pkg], alpm: &Alpm) -> Vec<Conflict> {
+    let local_pkgs = alpm.localdb().pkgs();
+    let names = pkgs.iter().map(|pkg| pkg.name()).collect::<HashSet<_>>();
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    let mut push = |conflict: Conflict| {
+        let known = conflicts.iter().any(|known| {
+            (known.package == conflict.package && known.conflicts_with == conflict.conflicts_with)
+                || (!conflict.installed
+                    && known.package == conflict.conflicts_with
+                    && known.conflicts_with == conflict.package)
+        });
+        if !known {
+            conflicts.push(conflict);
+        }
+    };
+
+    for pkg in pkgs {
+        let name = pkg.name();
+        for conflict in pkg.conflicts() {
+            if let Some(installed) = local_pkgs.find_satisfier(conflict.as_str()) {
+                if !names.contains(installed.name()) {
+                    push(Conflict {
+                        package: name.clone(),
+                        conflicts_with: installed.name().to_string(),
+                        installed: true,
+                    });
+                }
             }
+            for other in pkgs {
+                let other_name = other.name();
+                if other_name != name
+                    && satisfies(&other_name, &other.version(), &other.provides(), &conflict)
+                {
+                    push(Conflict {
+                        package: name.clone(),
+                        conflicts_with: other_name,
+                        installed: false,
+                    });
+                }
+            }
+        }
+    }
 
-            let mvp: Pkg;
-            let mut viable: Vec<Pkg>;
-            let mut maybe_viable: Vec<Pkg>;
-            for aur_pkg in res {
-                if aur_pkg.name == pkg_name {
+    // installed packages may be the ones declaring the conflict
+    for installed in local_pkgs.iter() {
+        if names.contains(installed.name()) {
+            continue;
+        }
+        for conflict in installed.conflicts() {
+            let conflict = conflict.to_string();
+            for pkg in pkgs {
+                if satisfies(&pkg.name(), &pkg.version(), &pkg.provides(), &conflict) {
+                    push(Conflict {
+                        package: pkg.name(),
+                        conflicts_with: installed.name().to_string(),
+                        installed: true,
+                    });
                 }
-                let leave = DepTree {
-                    pkg: aur_pkg.into(),
-                    dep_type: DepType::Dep,
-                    leaves: None,
-                    parent: Some(&mut branch as *mut DepTree<'a>), // further be dragons !
-                };
-                leaves.push(leave);
             }
-            //let res_bis = raur.search(pkg_info.0).await?;
-            //println!("could be satisfied by {res_bis:?}");
-            todo!()
         }
+    }
+
+    conflicts
+}
 
-        branch.leaves = Some(leaves);
+/// Whether a package named `name` at version `version`, providing `provides`, satisfies the
+/// dependency `dep` (e.g. "foo>=1.2").
+pub fn satisfies(name: &str, version: &str, provides: &[String], dep: &str) -> bool {
+    let (dep_name, dep_req) = parse_dependency(dep);
 
-        todo!()
+    if name == dep_name {
+        return match dep_req {
+            None => true,
+            Some((req_ord, req_ver)) => {
+                check_version_requirement(req_ord, req_ver, version).unwrap_or(false)
+            }
+        };
     }
+
+    provides.iter().any(|provide| {
+        let (provide_name, provide_ver) = parse_dependency(provide);
+        if provide_name != dep_name {
+            return false;
+        }
+        match (dep_req, provide_ver) {
+            (None, _) => true,
+            // an unversioned provide can't satisfy a versioned dependency
+            (Some(_), None) => false,
+            (Some((req_ord, req_ver)), Some((_, ver))) => {
+                check_version_requirement(req_ord, req_ver, ver).unwrap_or(false)
+            }
+        }
+    })
 }
 
-fn parse_dependency<'a>(pkg: &'a String) -> (&'a str, Option<(&'a str, &'a str)>) {
+pub fn parse_dependency<'a>(pkg: &'a str) -> (&'a str, Option<(&'a str, &'a str)>) {
     let sep_index = pkg.find(|c| c == '=' || c == '<' || c == '>');
 
     if sep_index == None {
@@ -220,5 +448,14 @@ fn parse_dependency<'a>(pkg: &'a String) -> (&'a str, Option<(&'a str, &'a str)>
 }
 
 fn check_version_requirement(req_ord: &str, req_ver: &str, ver: &str) -> Option<bool> {
-    todo!()
+    let ord = alpm::vercmp(ver, req_ver);
+
+    match req_ord {
+        "=" => Some(ord == Ordering::Equal),
+        ">=" => Some(ord != Ordering::Less),
+        "<=" => Some(ord != Ordering::Greater),
+        ">" => Some(ord == Ordering::Greater),
+        "<" => Some(ord == Ordering::Less),
+        _ => None,
+    }
 }
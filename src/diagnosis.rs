@@ -0,0 +1,161 @@
+use {
+    crate::{
+        colors::{self, Theme},
+        tr,
+    },
+    std::path::Path,
+};
+
+/// A known reason for makepkg to fail, recognized in its output.
+#[derive(Debug, PartialEq)]
+pub enum Cause {
+    /// PGP keys the sources are signed with that aren't in the keyring.
+    UnknownKeys(Vec<String>),
+    /// Source files whose checksum doesn't match the PKGBUILD's.
+    ChecksumMismatch(Vec<String>),
+    /// Dependencies makepkg found missing.
+    MissingDependencies(Vec<String>),
+    NoSpace,
+    /// Source files the server answered 404 for.
+    SourceNotFound(Vec<String>),
+}
+
+/// Remove the color codes makepkg may output.
+fn strip_colors(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip up to the final letter of the sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn push_unique(list: &mut Vec<String>, item: &str) {
+    if !item.is_empty() && !list.iter().any(|known| known == item) {
+        list.push(item.to_string());
+    }
+}
+
+/// What made the build logged in `log` fail, as far as it can be told.
+pub fn diagnose(log: &str) -> Vec<Cause> {
+    let lines = log.lines().map(strip_colors).collect::<Vec<_>>();
+
+    let mut keys = Vec::new();
+    let mut checksums = Vec::new();
+    let mut deps = Vec::new();
+    let mut no_space = false;
+    let mut not_found = Vec::new();
+    let mut got_404 = false;
+    let mut in_missing_deps = false;
+
+    for line in &lines {
+        let line = line.trim();
+
+        if in_missing_deps {
+            match line.strip_prefix("-> ") {
+                Some(dep) => {
+                    push_unique(&mut deps, dep.trim());
+                    continue;
+                }
+                None => in_missing_deps = false,
+            }
+        }
+
+        // "    foo.tar.gz ... FAILED (unknown public key 1234ABCD)"
+        if let Some((_, rest)) = line.split_once("unknown public key ") {
+            push_unique(&mut keys, rest.trim_end_matches(')').trim());
+        } else if let Some(file) = line.strip_suffix(" ... FAILED") {
+            push_unique(&mut checksums, file.trim());
+        } else if line.ends_with("Missing dependencies:") {
+            in_missing_deps = true;
+        } else if let Some(dep) = line.strip_prefix("error: target not found: ") {
+            push_unique(&mut deps, dep.trim());
+        } else if line.contains("No space left on device") {
+            no_space = true;
+        } else if line.contains("returned error: 404") {
+            got_404 = true;
+        } else if let Some(file) = line
+            .split_once("ERROR: Failure while downloading ")
+            .map(|(_, file)| file)
+        {
+            if got_404 {
+                push_unique(&mut not_found, file.trim());
+            }
+        }
+    }
+
+    let mut causes = Vec::new();
+    if !keys.is_empty() {
+        causes.push(Cause::UnknownKeys(keys));
+    }
+    if !checksums.is_empty() {
+        causes.push(Cause::ChecksumMismatch(checksums));
+    }
+    if !deps.is_empty() {
+        causes.push(Cause::MissingDependencies(deps));
+    }
+    if no_space {
+        causes.push(Cause::NoSpace);
+    }
+    if !not_found.is_empty() {
+        causes.push(Cause::SourceNotFound(not_found));
+    }
+    causes
+}
+
+/// Explain each of `causes` of the failed build of `pkgbase` and how to fix it, pointing at
+/// the full `log`.
+pub fn print(causes: &[Cause], pkgbase: &str, log: &Path) {
+    let Theme {
+        warning,
+        title,
+        dim,
+        clear,
+        ..
+    } = colors::theme();
+
+    for cause in causes {
+        let (problem, fix) = match cause {
+            Cause::UnknownKeys(keys) => (
+                tr!("diagnosis-unknown-keys", keys = keys.join(" ")),
+                tr!("fix-unknown-keys", keys = keys.join(" ")),
+            ),
+            Cause::ChecksumMismatch(files) => (
+                tr!("diagnosis-checksums", files = files.join(", ")),
+                tr!("fix-checksums", pkgbase = pkgbase),
+            ),
+            Cause::MissingDependencies(deps) => (
+                tr!("diagnosis-missing-deps", deps = deps.join(", ")),
+                tr!("fix-missing-deps", deps = deps.join(" ")),
+            ),
+            Cause::NoSpace => (tr!("diagnosis-no-space"), tr!("fix-no-space")),
+            Cause::SourceNotFound(files) => (
+                tr!("diagnosis-not-found", files = files.join(", ")),
+                tr!("fix-not-found", pkgbase = pkgbase),
+            ),
+        };
+        println!("{warning}::{clear} {title}{problem}{clear}");
+        println!("   {fix}");
+    }
+    println!(
+        "{dim}{}{clear}",
+        tr!("build-log", path = log.display().to_string())
+    );
+}
+
+/// The PGP keys `causes` tell are missing, if any.
+pub fn unknown_keys(causes: &[Cause]) -> Option<&[String]> {
+    causes.iter().find_map(|cause| match cause {
+        Cause::UnknownKeys(keys) => Some(keys.as_slice()),
+        _ => None,
+    })
+}
@@ -1,65 +1,354 @@
 use {
-    anyhow::{anyhow, Context, Result},
-    log::{debug, error, trace},
-    std::process::Command,
-    users::{get_current_uid, get_user_by_uid},
+    crate::{
+        cli::PacmanArgs,
+        colors::{self, Theme},
+        config::Config,
+        privilege::Escalation,
+        tr,
+    },
+    alpm::{Alpm, SigLevel},
+    anyhow::{anyhow, Result},
+    log::{debug, trace},
+    std::{
+        env, fs,
+        io::{self, Write},
+        path::PathBuf,
+        process::{Command, ExitStatus},
+    },
 };
 
-pub fn require_root() -> Result<()> {
-    let uid = get_current_uid();
+/// Outcome of one of the checks making sure rah runs somewhere it makes sense.
+pub struct ContextCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
 
-    debug!("Current uid is {uid}...");
+/// Parse os-release formatted `data` into its key/value pairs, unquoting values.
+pub fn parse_os_release(data: &str) -> Vec<(String, String)> {
+    data.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            (key.trim().to_string(), value.replace("\\\"", "\""))
+        })
+        .collect()
+}
 
-    if let Some(user) = get_user_by_uid(uid) {
-        debug!("...corresponding to user name {:?}", user.name());
-    } else {
-        error!("... but this uid doesn't correspond to any user");
-        return Err(anyhow!(
-            "Cannot identify current user (uid {uid}), aborting..."
-        ));
+/// Whether the `ID`/`ID_LIKE` fields of an os-release file describe Arch or a derivative.
+pub fn is_arch_based(os_release: &[(String, String)]) -> bool {
+    os_release.iter().any(|(key, value)| match key.as_str() {
+        "ID" => value == "arch",
+        "ID_LIKE" => value.split_whitespace().any(|id| id == "arch"),
+        _ => false,
+    })
+}
+
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())
+    })
+}
+
+fn find_libalpm() -> Option<PathBuf> {
+    ["/usr/lib", "/usr/lib64", "/lib", "/usr/local/lib"]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("libalpm.so"))
+        })
+}
+
+pub fn exec_context_report() -> Vec<ContextCheck> {
+    let mut report = Vec::new();
+
+    let os_release = ["/etc/os-release", "/usr/lib/os-release"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok().map(|data| (path, data)));
+    report.push(match os_release {
+        None => ContextCheck {
+            name: "os-release",
+            ok: false,
+            detail: tr!("check-os-release-unreadable"),
+        },
+        Some((path, data)) => {
+            let fields = parse_os_release(&data);
+            let field = |key: &str| {
+                fields
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.as_str())
+                    .unwrap_or("<unset>")
+                    .to_string()
+            };
+            ContextCheck {
+                name: "os-release",
+                ok: is_arch_based(&fields),
+                detail: tr!(
+                    "check-os-release",
+                    path = *path,
+                    id = field("ID"),
+                    id_like = field("ID_LIKE")
+                ),
+            }
+        }
+    });
+
+    for program in ["pacman", "makepkg"] {
+        let path = find_in_path(program);
+        report.push(ContextCheck {
+            name: program,
+            ok: path.is_some(),
+            detail: match path {
+                Some(path) => tr!("check-found-at", path = path.display().to_string()),
+                None => tr!("check-not-in-path"),
+            },
+        });
     }
 
-    if uid != 0 {
-        error!("Program should be run as root, returning with error...");
-        return Err(anyhow!("Program should be run as root, please launch it again with your favourite privilege escalation method !"));
+    let libalpm = find_libalpm();
+    report.push(ContextCheck {
+        name: "libalpm",
+        ok: libalpm.is_some(),
+        detail: match libalpm {
+            Some(path) => tr!("check-found-at", path = path.display().to_string()),
+            None => tr!("check-libalpm-missing"),
+        },
+    });
+
+    report
+}
+
+pub fn print_exec_context_report(report: &[ContextCheck]) {
+    let Theme {
+        ok,
+        error,
+        label,
+        clear,
+        ..
+    } = colors::theme();
+
+    let (passed, failed) = (tr!("check-ok"), tr!("check-failed"));
+    let width = passed.chars().count().max(failed.chars().count());
+
+    for check in report {
+        let (color, status) = if check.ok {
+            (ok, &passed)
+        } else {
+            (error, &failed)
+        };
+        eprintln!(
+            "  {color}{status:width$}{clear} {label}{}{clear} : {}",
+            check.name, check.detail
+        );
+    }
+}
+
+pub fn check_exec_context(conf: &Config) -> Result<()> {
+    let Theme { error, clear, .. } = colors::theme();
+
+    if conf.skip_distro_check {
+        debug!("Skipping execution context checks");
+        return Ok(());
+    }
+
+    // First check if the chap launching this is even using an arch-based distro, with the
+    // tooling rah relies on
+    let report = exec_context_report();
+
+    if report.iter().any(|check| !check.ok) {
+        eprintln!(
+            "{error}{}{clear} : {}",
+            tr!("error"),
+            tr!("context-checks-failed")
+        );
+        print_exec_context_report(&report);
+        eprintln!("{}", tr!("context-checks-skip"));
+        return Err(anyhow!(tr!("not-arch")));
     }
 
     Ok(())
 }
 
-pub fn check_exec_context() -> Result<()> {
-    // First check if the chap launching this is even using an arch-based distro
-    // TODO: more thourough checks an maybe allow running if pacman/makepkg is present ?
-    let data = std::fs::read_to_string("/etc/os-release")
-        .context( "Your distro is probably not an Arch-based distro, rah shouldn't be used on it. If untrue, please file an issue here https://github.com/kStor2poche/rah/issues\nAborting...")?;
+/// Open the pacman database described by the config, with its sync databases registered.
+pub fn alpm(conf: &Config) -> Result<Alpm> {
+    let Theme { warning, clear, .. } = colors::theme();
+
+    let alpm = Alpm::new(conf.pacman_root.as_str(), conf.pacman_db_path.as_str())?;
 
-    if !data.contains("arch") {
-        return Err(anyhow!("Your distro is probably not an Arch-based distro, rah shouldn't be used on it. If untrue, please file an issue here https://github.com/kStor2poche/rah/issues\nAborting..."));
+    // TODO: I think there's a better way to know which repos are used with pacman-conf -l (or
+    // with alpm but the config options don't seem to be implemented in the rust interface)
+    for repo in &conf.repos {
+        if let Err(err) = alpm.register_syncdb(repo.as_str(), SigLevel::NONE) {
+            eprintln!(
+                "{warning}{}{clear} : {}",
+                tr!("warning"),
+                tr!(
+                    "db-register-failed",
+                    repo = repo.as_str(),
+                    error = err.to_string()
+                )
+            );
+        }
     }
 
-    Ok(())
+    Ok(alpm)
 }
 
-pub fn split_pacman_aur(pkgs: Vec<String>) -> Result<(Option<Vec<String>>, Option<Vec<String>>)> {
-    // Used to know if package can be installed through pacman
-    let mut pacman_sync_check_args = pkgs
+/// Split `pkgs` between the ones found in the sync databases, to be installed by pacman, and
+/// the others, to be looked up in the AUR. `repo/pkg` targets always go to pacman.
+pub fn split_pacman_aur<'a>(alpm: &Alpm, pkgs: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    pkgs.iter()
+        .copied()
+        .partition(|pkg| pkg.contains('/') || alpm.syncdbs().iter().any(|db| db.pkg(*pkg).is_ok()))
+}
+
+/// Sync package called `name`, or `repo/name`.
+pub fn find_sync_pkg<'a>(alpm: &'a Alpm, name: &str) -> Option<&'a alpm::Package> {
+    match name.split_once('/') {
+        Some((repo, name)) => alpm
+            .syncdbs()
+            .iter()
+            .find(|db| db.name() == repo)?
+            .pkg(name)
+            .ok(),
+        None => alpm.syncdbs().iter().find_map(|db| db.pkg(name).ok()),
+    }
+}
+
+/// Run pacman with the arguments rah doesn't handle itself, as root if needed.
+pub fn forward_to_pacman(conf: &Config, args: &PacmanArgs) -> Result<ExitStatus> {
+    let mut cmd = if args.needs_root() {
+        Escalation::new(conf).command("pacman")
+    } else {
+        Command::new("pacman")
+    };
+    cmd.args(&args.forwarded);
+
+    debug!("forwarding to {cmd:?}");
+    Ok(cmd.status()?)
+}
+
+/// Run a command with inherited stdio, failing if it didn't exit successfully.
+pub fn run(cmd: &mut Command) -> Result<()> {
+    trace!("running {cmd:?}");
+    let status = cmd.status()?;
+    check_status(cmd, status)
+}
+
+/// The error of `cmd` having exited with `status`, if it failed.
+pub fn check_status(cmd: &Command, status: ExitStatus) -> Result<()> {
+    match status.code() {
+        None => Err(anyhow!(tr!(
+            "command-killed",
+            command = format!("{:?}", cmd.get_program())
+        ))),
+        Some(0) => Ok(()),
+        Some(code) => Err(anyhow!(tr!(
+            "command-failed",
+            command = format!("{:?}", cmd.get_program()),
+            code = code
+        ))),
+    }
+}
+
+/// pacman's answer to its questions, as a bit field, removing the installed packages that
+/// conflict with the ones being installed (ALPM_QUESTION_CONFLICT_PKG).
+pub const ASK_REMOVE_CONFLICTS: &str = "--ask=4";
+
+pub fn install_files(
+    escalation: &Escalation,
+    pkgs: &[PathBuf],
+    as_deps: bool,
+    flags: &[String],
+) -> Result<()> {
+    let mut cmd = escalation.command("pacman");
+    cmd.arg("-U");
+    if as_deps {
+        cmd.arg("--asdeps").args(without_reason_flags(flags));
+    } else {
+        cmd.args(flags);
+    }
+    run(cmd.args(pkgs))
+}
+
+/// `flags` without the ones choosing why packages get installed, for dependencies the user
+/// didn't ask for.
+pub fn without_reason_flags(flags: &[String]) -> impl Iterator<Item = &String> {
+    flags
         .iter()
-        .fold(String::from("(^"), |s1, s2| s1 + &"$|^".to_string() + &s2);
-    pacman_sync_check_args.push_str("$)");
-    let pacman_sync_check = Command::new("pacman")
-        .arg("-Ssq")
-        .arg(pacman_sync_check_args)
-        .output()?;
-    let output = String::from_utf8(pacman_sync_check.stdout)?;
-    let pacman_pkgs = match pacman_sync_check.status.code() {
-        None => {
-            return Err(anyhow!(
-                "Pacman command did not exit or was killed by a signal"
-            ));
-        }
-        Some(0) => Some(output.split("/").collect::<Vec<_>>()),
-        Some(_) => None,
+        .filter(|flag| !["--needed", "--asdeps", "--asexplicit"].contains(&flag.as_str()))
+}
+
+/// Ask a yes/no `question`, `default` being the answer to an empty line or a closed stdin.
+pub fn confirm(question: &str, default: bool) -> Result<bool> {
+    let Theme {
+        prompt,
+        title,
+        clear,
+        ..
+    } = colors::theme();
+
+    // answering in English always works, whatever the locale
+    let (yes, no) = (tr!("answer-yes"), tr!("answer-no"));
+    let choices = if default {
+        format!("[{}/{no}]", yes.to_uppercase())
+    } else {
+        format!("[{yes}/{}]", no.to_uppercase())
     };
+    print!("{prompt}::{clear} {title}{question} {choices}{clear} ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let answer = input.trim().to_lowercase();
+    Ok(match answer.chars().next() {
+        None => default,
+        Some(c) => c == 'y' || yes.starts_with(c),
+    })
+}
+
+/// `size` bytes, in a unit a human can read.
+pub fn human_size(size: i64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
 
-    todo!("{:?}", pacman_pkgs)
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} {}", UNITS[unit])
+    } else {
+        format!("{size:.2} {}", UNITS[unit])
+    }
+}
+
+/// Print `label : value` rows with their values aligned, whatever the length of the
+/// (translated) labels.
+pub fn print_fields(rows: &[(String, String)]) {
+    let Theme { label, clear, .. } = colors::theme();
+
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (name, value) in rows {
+        println!("{label}{name:width$} : {clear}{value}");
+    }
 }
@@ -0,0 +1,137 @@
+use {
+    fluent_bundle::{concurrent::FluentBundle, FluentResource},
+    log::{debug, warn},
+    std::{env, sync::OnceLock},
+    unic_langid::LanguageIdentifier,
+};
+
+pub use fluent_bundle::FluentArgs;
+
+/// Locale used when the requested one isn't translated, and for missing messages.
+pub const FALLBACK: &str = "en";
+
+/// Every translated locale, with its Fluent catalog.
+pub const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../i18n/en/rah.ftl")),
+    ("fr", include_str!("../i18n/fr/rah.ftl")),
+];
+
+/// The locale asked for by the environment, following the usual `LC_ALL` > `LC_MESSAGES` >
+/// `LANG` precedence.
+pub fn requested_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// The translated locale best matching a POSIX locale such as `fr_FR.UTF-8`, falling back to
+/// `FALLBACK`.
+pub fn negotiate(locale: &str) -> &'static str {
+    // drop the codeset and modifier, then use the language tag separator
+    let tag = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-");
+    let Ok(requested) = tag.parse::<LanguageIdentifier>() else {
+        return FALLBACK;
+    };
+
+    LOCALES
+        .iter()
+        .map(|(name, _)| *name)
+        .find(|name| {
+            name.parse::<LanguageIdentifier>()
+                .is_ok_and(|available| available.language == requested.language)
+        })
+        .unwrap_or(FALLBACK)
+}
+
+/// Looks messages up in a locale's catalog, then in the `FALLBACK` one.
+pub struct Localizer {
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    pub fn new(locale: &str) -> Self {
+        let mut names = vec![negotiate(locale)];
+        if names[0] != FALLBACK {
+            names.push(FALLBACK);
+        }
+
+        let bundles = names
+            .into_iter()
+            .filter_map(|name| {
+                let (_, source) = LOCALES.iter().find(|(locale, _)| *locale == name)?;
+                let resource = FluentResource::try_new(source.to_string())
+                    .map_err(|(_, errs)| warn!("Errors in the {name} catalog : {errs:?}"))
+                    .ok()?;
+                let mut bundle = FluentBundle::new_concurrent(vec![name.parse().ok()?]);
+                // the unicode isolation marks show up as garbage in most terminals
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(resource)
+                    .map_err(|errs| warn!("Errors in the {name} catalog : {errs:?}"))
+                    .ok()?;
+                Some(bundle)
+            })
+            .collect();
+
+        Localizer { bundles }
+    }
+
+    /// Whether the preferred locale's catalog has a message for `id`.
+    pub fn has_message(&self, id: &str) -> bool {
+        self.bundles
+            .first()
+            .is_some_and(|bundle| bundle.has_message(id))
+    }
+
+    /// The message `id` formatted with `args`, or `id` itself if no catalog has it.
+    pub fn tr(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(id).and_then(|msg| msg.value()) else {
+                continue;
+            };
+            let mut errs = Vec::new();
+            let msg = bundle.format_pattern(pattern, args, &mut errs);
+            if !errs.is_empty() {
+                debug!("Errors formatting message {id} : {errs:?}");
+            }
+            return msg.into_owned();
+        }
+
+        warn!("Missing translation for {id}");
+        id.to_string()
+    }
+}
+
+static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+
+/// Set up translations according to the environment, to be called before printing anything.
+pub fn init() {
+    let locale = requested_locale().unwrap_or_default();
+    debug!("Using locale \"{}\" for \"{locale}\"", negotiate(&locale));
+    let _ = LOCALIZER.set(Localizer::new(&locale));
+}
+
+/// The message `id` in the current locale, see `tr!` for a shorter form.
+pub fn tr(id: &str, args: Option<&FluentArgs>) -> String {
+    LOCALIZER
+        .get_or_init(|| Localizer::new(FALLBACK))
+        .tr(id, args)
+}
+
+/// Translate a message, e.g. `tr!("found-packages", count = hits.len())`.
+#[macro_export]
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::tr($id, None)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::tr($id, Some(&args))
+    }};
+}
@@ -0,0 +1,26 @@
+pub mod aur;
+pub mod aur_cache;
+pub mod aur_scheduler;
+pub mod cli;
+pub mod colors;
+pub mod completions;
+pub mod config;
+pub mod config_schema;
+pub mod database;
+pub mod dep_graph;
+pub mod dep_tree;
+pub mod diagnosis;
+pub mod helpers;
+pub mod i18n;
+pub mod local_repo;
+pub mod makepkg;
+pub mod menu;
+pub mod output;
+pub mod pgp;
+pub mod plan;
+pub mod privilege;
+pub mod query;
+pub mod reverse_deps;
+pub mod sync;
+pub mod template;
+pub mod transaction;
@@ -0,0 +1,70 @@
+use {
+    crate::{config::Config, helpers},
+    anyhow::Result,
+    log::info,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        process::Command,
+    },
+};
+
+/// A pacman repository living in the cache directory, holding every package built by rah so
+/// that other machines or chroots can use them and so that downgrades don't need a rebuild.
+pub struct LocalRepo {
+    name: String,
+    path: PathBuf,
+}
+
+impl LocalRepo {
+    pub fn new(conf: &Config) -> Self {
+        LocalRepo {
+            name: conf.local_repo_name.clone(),
+            path: Path::new(&conf.cache_path).join("repo"),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.path.join(format!("{}.db.tar.gz", self.name))
+    }
+
+    pub fn init(&self) -> Result<()> {
+        fs::create_dir_all(&self.path)?;
+        Ok(())
+    }
+
+    /// Add (or update) packages in the repository database. Older versions of the added
+    /// packages are removed from the database but their files are kept around for downgrades.
+    pub fn add(&self, pkgs: &[PathBuf]) -> Result<()> {
+        if pkgs.is_empty() {
+            return Ok(());
+        }
+
+        self.init()?;
+        info!("Adding {pkgs:?} to local repository {}", self.name);
+
+        helpers::run(
+            Command::new("repo-add")
+                .arg("--quiet")
+                .arg(self.db_path())
+                .args(pkgs),
+        )
+    }
+
+    /// Snippet to put in a pacman.conf to consume this repository.
+    pub fn pacman_conf_entry(&self) -> String {
+        format!(
+            "[{}]\nSigLevel = Optional TrustAll\nServer = file://{}",
+            self.name,
+            self.path.display()
+        )
+    }
+}
@@ -1,22 +1,22 @@
-mod colors;
-mod config;
-mod database;
-mod dep_tree;
-mod helpers;
-mod query;
-mod sync;
-
 use {
-    crate::config::Config,
-    anyhow::Result,
-    clap::{Arg, ArgAction, Command},
+    anyhow::{anyhow, Result},
+    clap::ArgMatches,
     log::info,
-    std::env,
+    rah::{
+        cli::{self, PacmanArgs},
+        colors::{self, ColorMode},
+        completions,
+        config::Config,
+        helpers, i18n, menu,
+        output::OutputFormat,
+        query, reverse_deps,
+        sync::{self, SearchOptions, SortBy, SyncOptions},
+        tr,
+    },
+    std::{env, io, process},
     tokio,
 };
 
-const VERSION: &str = "0.0.1";
-
 #[tokio::main]
 async fn main() -> Result<()> {
     if env::var("RUST_LOG").is_err() {
@@ -27,120 +27,183 @@ async fn main() -> Result<()> {
         env_logger::init();
     }
 
-    helpers::check_exec_context()?;
-
-    let command_matches = Command::new("rah")
-        .about("rah - the Rusty AUR Helper !")
-        .version(VERSION)
-        .subcommand_required(true)
-        .arg_required_else_help(true)
-        .arg(
-            Arg::new("config")
-                .short('c')
-                .long("config")
-                .value_name("FILE")
-                .help("Choose a specific config file"),
-        )
-        .subcommand(
-            Command::new("query")
-                .short_flag('Q')
-                .long_flag("query")
-                .about("Query the local package database")
-                .arg(
-                    Arg::new("search")
-                        .short('s')
-                        .long("search")
-                        .help("Search for matching packages in the local package database")
-                        .conflicts_with("info")
-                        .action(ArgAction::Set)
-                        .num_args(1..),
-                )
-                .arg(
-                    Arg::new("info")
-                        .short('i')
-                        .long("info")
-                        .help("Get package info from the local package database")
-                        .conflicts_with("search")
-                        .action(ArgAction::Set)
-                        .num_args(1..),
-                ),
-        )
-        .subcommand(
-            Command::new("sync")
-                .short_flag('S')
-                .long_flag("sync")
-                .about("Synchronize packages with the AUR")
-                .arg(
-                    Arg::new("search")
-                        .short('s')
-                        .long("search")
-                        .help("Search for matching packages in the AUR")
-                        .conflicts_with("info")
-                        .action(ArgAction::Set)
-                        .num_args(1..),
-                )
-                .arg(
-                    Arg::new("info")
-                        .short('i')
-                        .long("info")
-                        .help("Get package info from the AUR")
-                        .conflicts_with("search")
-                        .action(ArgAction::Set)
-                        .num_args(1..),
-                )
-                .arg(
-                    Arg::new("package")
-                        .help("packages")
-                        .required_unless_present("search")
-                        .required_unless_present("info")
-                        .action(ArgAction::Set)
-                        .num_args(1..),
-                ),
-        )
-        .get_matches();
+    i18n::init();
 
-    let mut conf = Config::default();
+    let cli = cli::command();
+    let args = env::args().collect::<Vec<_>>();
+    let pacman_args = PacmanArgs::parse(&cli, &args[1..]);
 
-    if let Some(conf_path) = command_matches.get_one::<String>("config") {
-        conf.config_path = conf_path.to_string();
+    // whatever rah doesn't do itself goes to pacman, so that rah can be aliased over it
+    if !pacman_args.handled_by_rah() {
+        let own_matches = cli
+            .arg_required_else_help(false)
+            .get_matches_from(args.iter().take(1).chain(&pacman_args.own));
+        let conf = configure(&own_matches)?;
+        helpers::check_exec_context(&conf)?;
+        let status = helpers::forward_to_pacman(&conf, &pacman_args)?;
+        process::exit(status.code().unwrap_or(1));
     }
 
-    info!("Getting config from \"{}\"...", conf.config_path);
+    let command_matches = cli.get_matches_from(&args);
+    let mut conf = configure(&command_matches)?;
+
+    if let Some(prefix) = command_matches.get_one::<String>("complete-aur") {
+        for name in completions::complete_packages(&conf, prefix) {
+            println!("{name}");
+        }
+        return Ok(());
+    }
+    if let Some(("completions", completions_matches)) = command_matches.subcommand() {
+        let shell = completions_matches.get_one::<String>("shell").unwrap();
+        return completions::generate(shell, &mut io::stdout());
+    }
 
-    //conf.parse()?;
+    helpers::check_exec_context(&conf)?;
+
+    if command_matches.get_flag("resume") {
+        return sync::resume(&conf).await;
+    }
 
     match command_matches.subcommand() {
+        Some(("whyneeded", whyneeded_matches)) => {
+            let packages = whyneeded_matches
+                .get_many::<String>("package")
+                .unwrap()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>();
+            reverse_deps::print(packages, &conf).await?;
+        }
         Some(("query", query_matches)) => {
             if let Some(packages) = query_matches.get_many::<String>("search") {
                 let packages = packages.map(|s| s.as_str()).collect::<Vec<_>>();
-                query::search(packages);
+                query::search(packages, &conf)?;
             }
             if let Some(packages) = query_matches.get_many::<String>("info") {
-                let comma_sep = packages.map(|s| s.as_str()).collect::<Vec<_>>().join(", ");
-                println!("Retrieving info for {comma_sep}...");
-            } else if let Some(packages) = query_matches.get_many::<String>("") {
-                let comma_sep = packages.map(|s| s.as_str()).collect::<Vec<_>>().join(", ");
-                println!("Seeking db for {comma_sep}...");
+                let packages = packages
+                    .chain(
+                        query_matches
+                            .get_many::<String>("package")
+                            .into_iter()
+                            .flatten(),
+                    )
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>();
+                if packages.is_empty() {
+                    return Err(anyhow!(tr!("no-targets")));
+                }
+                if query_matches.get_flag("required-by") {
+                    reverse_deps::print(packages, &conf).await?;
+                } else {
+                    query::info(packages, &conf)?;
+                }
             }
         }
+        Some(("deptree", deptree_matches)) => {
+            let packages = deptree_matches
+                .get_many::<String>("package")
+                .unwrap()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>();
+            sync::deptree(packages, &conf, false, deptree_matches.get_flag("dot")).await?;
+        }
         Some(("sync", query_matches)) => {
+            let refresh = query_matches.get_flag("refresh");
+            for flag in ["needed", "asdeps", "asexplicit", "noconfirm"] {
+                if query_matches.get_flag(flag) {
+                    conf.pacman_flags.push(format!("--{flag}"));
+                }
+            }
             if let Some(packages) = query_matches.get_many::<String>("search") {
                 let packages = packages.map(|s| s.as_str()).collect::<Vec<_>>();
-                sync::search(packages).await?;
+                let opts = SearchOptions {
+                    by: sync::parse_search_by(query_matches.get_one::<String>("by").unwrap())?,
+                    sort_by: query_matches
+                        .get_one::<String>("sortby")
+                        .map(|sort_by| SortBy::parse(sort_by))
+                        .transpose()?,
+                    reverse: query_matches.get_flag("reverse"),
+                    limit: query_matches.get_one::<usize>("limit").copied(),
+                    no_outdated: query_matches.get_flag("no-outdated"),
+                    maintained_only: query_matches.get_flag("maintained-only"),
+                    min_votes: query_matches
+                        .get_one::<u32>("min-votes")
+                        .copied()
+                        .unwrap_or(0),
+                };
+                sync::search(packages, &conf, refresh, &opts).await?;
             }
             if let Some(packages) = query_matches.get_many::<String>("info") {
-                let packages = packages.map(|s| s.as_str()).collect::<Vec<_>>();
-                sync::info(packages).await?;
+                let packages = packages
+                    .chain(
+                        query_matches
+                            .get_many::<String>("package")
+                            .into_iter()
+                            .flatten(),
+                    )
+                    .map(|s| s.as_str())
+                    .collect::<Vec<_>>();
+                if packages.is_empty() {
+                    return Err(anyhow!(tr!("no-targets")));
+                }
+                if query_matches.get_flag("graph") {
+                    sync::deptree(packages, &conf, refresh, query_matches.get_flag("dot")).await?;
+                } else {
+                    sync::info(packages, &conf, refresh).await?;
+                }
             } else if let Some(packages) = query_matches.get_many::<String>("package") {
                 let packages = packages.map(|s| s.as_str()).collect::<Vec<_>>();
-                sync::sync(packages).await?;
+                let opts = SyncOptions {
+                    refresh,
+                    print_plan: query_matches.get_flag("print-plan"),
+                };
+                sync::sync(packages, &conf, &opts).await?;
             }
         }
         Some((command, _)) => {
-            println!("Command \"{}\" not found.", command);
+            println!("{}", tr!("command-not-found", command = command));
+        }
+        None => {
+            if let Some(terms) = command_matches.get_many::<String>("targets") {
+                let terms = terms.map(|s| s.as_str()).collect::<Vec<_>>();
+                menu::search_install(terms, &conf).await?;
+            }
         }
-        None => {}
     }
 
     Ok(())
 }
+
+/// The config, with the options given on the command line applied.
+fn configure(matches: &ArgMatches) -> Result<Config> {
+    let mut conf = Config::default();
+
+    if let Some(conf_path) = matches.get_one::<String>("config") {
+        conf.config_path = conf_path.to_string();
+    }
+
+    info!("Getting config from \"{}\"...", conf.config_path);
+
+    conf.parse(None)?;
+
+    if matches.get_flag("offline") {
+        conf.offline = true;
+    }
+    if matches.get_flag("json") {
+        conf.output_format = OutputFormat::Json;
+    } else if let Some(format) = matches.get_one::<String>("format") {
+        conf.output_format = OutputFormat::parse(format)?;
+    }
+    if let Some(color) = matches.get_one::<String>("color") {
+        conf.color = ColorMode::parse(color)?;
+    }
+    if let Some(jobs) = matches.get_one::<usize>("jobs") {
+        conf.build_jobs = *jobs;
+    }
+    if matches.get_flag("skip-distro-check") {
+        conf.skip_distro_check = true;
+    }
+
+    colors::init(&conf);
+
+    Ok(conf)
+}
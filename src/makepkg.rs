@@ -0,0 +1,218 @@
+use {
+    crate::{
+        config::Config,
+        helpers,
+        local_repo::LocalRepo,
+        privilege::{self, BuildUser},
+    },
+    anyhow::{anyhow, Result},
+    log::{debug, info, trace},
+    std::{
+        fs::{self, File},
+        io::{self, BufRead, BufReader, Read, Write},
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+        sync::{Arc, Mutex},
+        thread,
+    },
+};
+
+/// Directory where the AUR git repository of `pkgbase` gets cloned.
+pub fn clone_dir(conf: &Config, pkgbase: &str) -> PathBuf {
+    Path::new(&conf.cache_path).join("clone").join(pkgbase)
+}
+
+/// Log of the last build of `pkgbase`.
+pub fn log_path(conf: &Config, pkgbase: &str) -> PathBuf {
+    Path::new(&conf.cache_path)
+        .join("logs")
+        .join(format!("{pkgbase}.log"))
+}
+
+/// Directory where built packages end up : the local repository if it is enabled, a plain
+/// package cache otherwise.
+pub fn pkgdest(conf: &Config) -> PathBuf {
+    if conf.local_repo {
+        LocalRepo::new(conf).path().to_path_buf()
+    } else {
+        Path::new(&conf.cache_path).join("pkg")
+    }
+}
+
+pub fn clone_or_pull(
+    conf: &Config,
+    pkgbase: &str,
+    build_user: Option<&BuildUser>,
+) -> Result<PathBuf> {
+    let dir = clone_dir(conf, pkgbase);
+
+    let clone_root = dir.parent().unwrap_or(&dir);
+    match build_user {
+        Some(build_user) => build_user.own(clone_root)?,
+        None => fs::create_dir_all(clone_root)?,
+    }
+
+    if dir.join(".git").exists() {
+        info!("Pulling {pkgbase} in {dir:?}...");
+        helpers::run(
+            privilege::build_command(build_user, "git")
+                .arg("-C")
+                .arg(&dir)
+                .args(["pull", "--ff-only", "--quiet"]),
+        )?;
+    } else {
+        info!("Cloning {pkgbase} into {dir:?}...");
+        helpers::run(
+            privilege::build_command(build_user, "git")
+                .args(["clone", "--quiet"])
+                .arg(conf.aur_endpoint(&format!("{pkgbase}.git")))
+                .arg(&dir),
+        )?;
+    }
+
+    Ok(dir)
+}
+
+/// Name of the package contained in a package file, following makepkg's
+/// `{pkgname}-{pkgver}-{pkgrel}-{arch}.pkg.tar.*` naming.
+pub fn pkg_file_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let stem = &file_name[..file_name.find(".pkg.tar")?];
+    let mut parts = stem.rsplitn(4, '-');
+    let (_arch, _pkgrel, _pkgver) = (parts.next()?, parts.next()?, parts.next()?);
+    parts.next().map(|name| name.to_string())
+}
+
+/// Ask makepkg which package files building `dir` would produce in `pkgdest`.
+pub fn package_list(
+    dir: &Path,
+    pkgdest: &Path,
+    build_user: Option<&BuildUser>,
+) -> Result<Vec<PathBuf>> {
+    let output = privilege::build_command(build_user, "makepkg")
+        .arg("--packagelist")
+        .current_dir(dir)
+        .env("PKGDEST", pkgdest)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "makepkg --packagelist failed in {dir:?} : {}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Copy everything `from` outputs to `to` as well as to `log`, each line shown starting with
+/// `prefix` if any.
+fn tee(
+    from: impl Read,
+    mut to: impl Write,
+    log: Arc<Mutex<File>>,
+    prefix: Option<String>,
+) -> io::Result<()> {
+    if let Some(prefix) = prefix {
+        let mut from = BufReader::new(from);
+        let mut line = Vec::new();
+        while from.read_until(b'\n', &mut line)? > 0 {
+            let mut shown = prefix.as_bytes().to_vec();
+            shown.extend_from_slice(&line);
+            // a line at once, so that the builds running alongside don't cut it
+            to.write_all(&shown)?;
+            if let Ok(mut log) = log.lock() {
+                log.write_all(&line)?;
+            }
+            line.clear();
+        }
+        return Ok(());
+    }
+
+    let mut from = from;
+    let mut buf = [0; 8192];
+    loop {
+        let n = from.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        to.write_all(&buf[..n])?;
+        to.flush()?;
+        if let Ok(mut log) = log.lock() {
+            log.write_all(&buf[..n])?;
+        }
+    }
+}
+
+/// Run `cmd` like `helpers::run`, its output shown (each line starting with `prefix` if any)
+/// and written to `log`.
+fn run_logged(cmd: &mut Command, log: &Path, prefix: Option<&str>) -> Result<()> {
+    trace!("running {cmd:?}, logging to {log:?}");
+    if let Some(parent) = log.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = Arc::new(Mutex::new(File::create(log)?));
+
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let prefix = prefix.map(|prefix| prefix.to_string());
+    let stdout = child.stdout.take().map(|out| {
+        let (file, prefix) = (file.clone(), prefix.clone());
+        thread::spawn(move || tee(out, io::stdout(), file, prefix))
+    });
+    let stderr = child.stderr.take().map(|err| {
+        let (file, prefix) = (file.clone(), prefix.clone());
+        thread::spawn(move || tee(err, io::stderr(), file, prefix))
+    });
+    let status = child.wait()?;
+    for handle in stdout.into_iter().chain(stderr) {
+        if let Ok(Err(err)) = handle.join() {
+            debug!("failed to log the output of {cmd:?} : {err}");
+        }
+    }
+
+    helpers::check_status(cmd, status)
+}
+
+/// Build the PKGBUILD in `dir`, unless every package it produces is already in `pkgdest`,
+/// makepkg's output going to `log` as well and its lines starting with `prefix`, if any, when
+/// other builds run alongside. Returns the paths of the built packages.
+pub fn build(
+    dir: &Path,
+    pkgdest: &Path,
+    log: &Path,
+    build_user: Option<&BuildUser>,
+    prefix: Option<&str>,
+) -> Result<Vec<PathBuf>> {
+    match build_user {
+        Some(build_user) => build_user.own(pkgdest)?,
+        None => fs::create_dir_all(pkgdest)?,
+    }
+
+    let pkgs = package_list(dir, pkgdest, build_user)?;
+    if !pkgs.is_empty() && pkgs.iter().all(|pkg| pkg.exists()) {
+        debug!("{pkgs:?} already built, skipping build");
+        return Ok(pkgs);
+    }
+
+    privilege::check_build_user(build_user)?;
+
+    run_logged(
+        privilege::build_command(build_user, "makepkg")
+            .args(["--force", "--cleanbuild", "--noconfirm"])
+            .current_dir(dir)
+            .env("PKGDEST", pkgdest),
+        log,
+        prefix,
+    )?;
+
+    let missing = pkgs.iter().filter(|pkg| !pkg.exists()).collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(anyhow!("makepkg did not produce {missing:?}"));
+    }
+
+    Ok(pkgs)
+}
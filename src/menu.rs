@@ -0,0 +1,142 @@
+use {
+    crate::{
+        aur::Aur,
+        colors::{self, Theme},
+        config::Config,
+        helpers,
+        sync::{self, SearchOptions, SyncOptions},
+        tr,
+    },
+    anyhow::{anyhow, Result},
+    log::trace,
+    std::io::{self, Write},
+};
+
+/// How well `pkg` matches the search `terms`, higher being better.
+fn relevance(pkg: &raur::Package, terms: &[&str]) -> (u8, u32) {
+    let name = pkg.name.to_lowercase();
+    let score = terms
+        .iter()
+        .map(|term| term.to_lowercase())
+        .map(|term| {
+            if name == term {
+                3
+            } else if name.starts_with(&term) {
+                2
+            } else if name.contains(&term) {
+                1
+            } else {
+                0
+            }
+        })
+        .max()
+        .unwrap_or(0);
+
+    (score, pkg.num_votes)
+}
+
+/// Parse a selection such as `1 3-5 ^4` into sorted, deduplicated 1-based indices out of `max`.
+/// Only giving exclusions (`^2`) selects everything else.
+pub fn parse_selection(input: &str, max: usize) -> Result<Vec<usize>> {
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
+
+    for token in input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+    {
+        let (list, range) = match token.strip_prefix('^') {
+            Some(range) => (&mut excluded, range),
+            None => (&mut included, token),
+        };
+        let parse = |n: &str| {
+            n.parse::<usize>()
+                .ok()
+                .filter(|n| (1..=max).contains(n))
+                .ok_or_else(|| anyhow!(tr!("invalid-selection", selection = token, max = max)))
+        };
+        match range.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                list.extend(start.min(end)..=start.max(end));
+            }
+            None => list.push(parse(range)?),
+        }
+    }
+
+    if included.is_empty() && !excluded.is_empty() {
+        included.extend(1..=max);
+    }
+    included.retain(|n| !excluded.contains(n));
+    included.sort();
+    included.dedup();
+
+    Ok(included)
+}
+
+/// `rah foo` : search the AUR, let the user pick among the results and install them.
+pub async fn search_install(terms: Vec<&str>, conf: &Config) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        number,
+        name,
+        version,
+        dim,
+        prompt,
+        clear,
+        ..
+    } = colors::theme();
+
+    trace!("interactive search for {terms:?}");
+
+    let aur = Aur::new(conf, false).await?;
+    let mut hits = sync::search_hits(&aur, &terms, &SearchOptions::default()).await?;
+
+    if hits.is_empty() {
+        println!(
+            "{header}::{clear} {title}{}{clear}",
+            tr!("no-package-found")
+        );
+        return Ok(());
+    }
+
+    // best match last, right above the prompt, and numbered 1
+    hits.sort_by_key(|pkg| relevance(pkg, &terms));
+
+    let alpm = helpers::alpm(conf)?;
+    let localdb = alpm.localdb();
+    for (i, pkg) in hits.iter().enumerate() {
+        println!(
+            "{number}{}{clear} {name}{}{clear} {version}{}{clear}{}{clear}\n    {}",
+            hits.len() - i,
+            pkg.name,
+            pkg.version,
+            sync::format_flags(sync::search_flags(pkg, localdb)),
+            pkg.description
+                .clone()
+                .unwrap_or(format!("{dim}{}{clear}", tr!("no-description")))
+        );
+    }
+
+    print!(
+        "{prompt}==>{clear} {title}{}{clear}\n{prompt}==>{clear} ",
+        tr!("menu-prompt")
+    );
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let selection = parse_selection(&input, hits.len())?;
+    if selection.is_empty() {
+        println!("{header}::{clear} {title}{}{clear}", tr!("nothing-to-do"));
+        return Ok(());
+    }
+
+    let packages = selection
+        .iter()
+        .map(|n| hits[hits.len() - n].name.as_str())
+        .collect::<Vec<_>>();
+
+    sync::sync(packages, conf, &SyncOptions::default()).await
+}
@@ -0,0 +1,126 @@
+use {
+    crate::{colors, config::Config, template::Template},
+    anyhow::{anyhow, Result},
+    serde::Serialize,
+    std::io::{self, Write},
+};
+
+/// Version of the records' schema, bumped whenever a field is renamed, removed or changes
+/// meaning. Adding fields doesn't bump it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Kinds of records, each of them possibly getting its own template in the config.
+pub const KINDS: &[&str] = &[
+    "aur_search",
+    "aur_info",
+    "local_search",
+    "local_info",
+    "plan",
+    "reverse_deps",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Human readable, colored output.
+    Text,
+    /// A single JSON document holding every record.
+    Json,
+    /// One JSON record per line.
+    Ndjson,
+    /// One user-defined line per record.
+    Template(Template),
+}
+
+impl OutputFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            template if template.contains('{') => Ok(OutputFormat::Template(Template::parse(template)?)),
+            other => Err(anyhow!(
+                "Unknown output format \"{other}\", expected one of text, json, ndjson or a template"
+            )),
+        }
+    }
+}
+
+/// Whether records of `kind` are printed through `emit` rather than by the command's own
+/// text output.
+pub fn is_custom(conf: &Config, kind: &str) -> bool {
+    conf.output_format != OutputFormat::Text || conf.templates.contains_key(kind)
+}
+
+#[derive(Serialize)]
+struct Document<'a, T> {
+    schema_version: u32,
+    kind: &'a str,
+    results: &'a [T],
+}
+
+#[derive(Serialize)]
+struct Line<'a, T> {
+    schema_version: u32,
+    kind: &'a str,
+    #[serde(flatten)]
+    record: &'a T,
+}
+
+/// Write `records` of the given `kind` to stdout, in the format chosen on the command line or
+/// through the template configured for `kind`.
+pub fn emit<T: Serialize>(conf: &Config, kind: &str, records: &[T]) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+
+    match &conf.output_format {
+        OutputFormat::Text => match conf.templates.get(kind) {
+            Some(template) => render(&mut stdout, template, records, colors::theme().enabled)?,
+            None => return Err(anyhow!("No template configured for {kind} records")),
+        },
+        OutputFormat::Template(template) => {
+            render(&mut stdout, template, records, colors::theme().enabled)?
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(
+                &mut stdout,
+                &Document {
+                    schema_version: SCHEMA_VERSION,
+                    kind,
+                    results: records,
+                },
+            )?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(
+                    &mut stdout,
+                    &Line {
+                        schema_version: SCHEMA_VERSION,
+                        kind,
+                        record,
+                    },
+                )?;
+                writeln!(stdout)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn render<T: Serialize>(
+    out: &mut impl Write,
+    template: &Template,
+    records: &[T],
+    color: bool,
+) -> Result<()> {
+    for record in records {
+        writeln!(
+            out,
+            "{}",
+            template.render(&serde_json::to_value(record)?, color)?
+        )?;
+    }
+
+    Ok(())
+}
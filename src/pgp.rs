@@ -0,0 +1,237 @@
+use {
+    crate::{
+        config::Config,
+        helpers,
+        privilege::{self, BuildUser},
+    },
+    anyhow::{anyhow, Context, Result},
+    log::debug,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+        process::{Child, Command, Stdio},
+    },
+};
+
+const TRUSTED_KEYS_FILE: &str = "trusted_keys.json";
+
+/// The `validpgpkeys` of the `.SRCINFO` in `dir`, none if there is no `.SRCINFO`.
+pub fn srcinfo_keys(dir: &Path) -> Result<Vec<String>> {
+    let path = dir.join(".SRCINFO");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let srcinfo = fs::read_to_string(&path)?;
+    Ok(parse_srcinfo_keys(&srcinfo))
+}
+
+/// The `validpgpkeys` entries of `srcinfo`, in order and without duplicates.
+pub fn parse_srcinfo_keys(srcinfo: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    for line in srcinfo.lines() {
+        let Some((field, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = value.trim().to_string();
+        if field.trim() == "validpgpkeys" && !key.is_empty() && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+/// gpg run by whoever builds packages, so that it uses the keyring makepkg checks signatures
+/// against.
+fn gpg(build_user: Option<&BuildUser>) -> Command {
+    let mut cmd = privilege::build_command(build_user, "gpg");
+    cmd.arg("--batch");
+    cmd
+}
+
+/// The ones of `keys` missing from the keyring of whoever builds packages.
+pub fn missing_keys(keys: &[String], build_user: Option<&BuildUser>) -> Result<Vec<String>> {
+    let mut missing = Vec::new();
+    for key in keys {
+        let status = gpg(build_user)
+            .args(["--list-keys", key])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if !status.success() {
+            missing.push(key.clone());
+        }
+    }
+    Ok(missing)
+}
+
+/// `text` with the bytes escaped as `escape` followed by two hex digits decoded.
+fn unescape(text: &str, escape: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while !rest.is_empty() {
+        let byte = rest
+            .strip_prefix(escape.as_bytes())
+            .and_then(|hex| hex.get(..2))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match byte {
+            Some(byte) => {
+                bytes.push(byte);
+                rest = &rest[escape.len() + 2..];
+            }
+            None => {
+                bytes.push(rest[0]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// The user ID of the first key of gpg's `--with-colons` output: field 9 of a keyring listing,
+/// escaped as `\xHH`, or field 1 of a keyserver search, escaped as `%XX`.
+pub fn colons_uid(output: &[u8], field: usize) -> Option<String> {
+    let escape = if field == 1 { "%" } else { "\\x" };
+    String::from_utf8_lossy(output)
+        .lines()
+        .find(|line| line.starts_with("uid:"))
+        .and_then(|line| line.split(':').nth(field))
+        .filter(|uid| !uid.is_empty())
+        .map(|uid| unescape(uid, escape))
+}
+
+/// Who `key` belongs to, looked up in the configured keyring file and then on the configured
+/// keyserver. None when neither is configured or neither knows the key.
+pub fn uid(key: &str, conf: &Config, build_user: Option<&BuildUser>) -> Option<String> {
+    if let Some(keyring) = &conf.pgp_keyring {
+        let output = gpg(build_user)
+            .args(["--no-default-keyring", "--keyring", keyring])
+            .args(["--with-colons", "--list-keys", key])
+            .output()
+            .ok()?;
+        if let Some(uid) = colons_uid(&output.stdout, 9) {
+            return Some(uid);
+        }
+    }
+
+    let keyserver = conf.pgp_keyserver.as_ref()?;
+    let output = gpg(build_user)
+        .args(["--keyserver", keyserver])
+        .args(["--with-colons", "--search-keys", key])
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    colons_uid(&output.stdout, 1)
+}
+
+/// The ones of `keys`, fingerprints or long key IDs, that none of the keys of gpg's
+/// `--with-colons` listing `output` has for fingerprint.
+pub fn missing_from_listing(keys: &[String], output: &[u8]) -> Vec<String> {
+    let listing = String::from_utf8_lossy(output);
+    // subkeys included, makepkg accepts signatures made with them
+    let fingerprints = listing
+        .lines()
+        .filter(|line| line.starts_with("fpr:"))
+        .filter_map(|line| line.split(':').nth(9))
+        .filter(|fpr| !fpr.is_empty())
+        .collect::<Vec<_>>();
+    keys.iter()
+        .filter(|key| {
+            let key = key.trim_start_matches("0x").replace(' ', "").to_uppercase();
+            !fingerprints.iter().any(|fpr| fpr.ends_with(&key))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Spawn `cmd` with `input` written to its stdin.
+fn spawn_with_input(cmd: &mut Command, input: &[u8]) -> Result<Child> {
+    let mut child = cmd.stdin(Stdio::piped()).spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("no stdin for {cmd:?}"))?
+        .write_all(input)?;
+    Ok(child)
+}
+
+/// Import `keys` into the keyring of whoever builds packages, from the configured keyring file
+/// if it has them all, from the keyserver otherwise.
+pub fn import(keys: &[String], conf: &Config, build_user: Option<&BuildUser>) -> Result<()> {
+    if let Some(keyring) = &conf.pgp_keyring {
+        let exported = gpg(build_user)
+            .args(["--no-default-keyring", "--keyring", keyring, "--export"])
+            .args(keys)
+            .output()?;
+        // gpg exports whatever keys it found, even when missing some, so look at what it did
+        if exported.status.success() && !exported.stdout.is_empty() {
+            let mut cmd = gpg(build_user);
+            cmd.args(["--with-colons", "--import-options", "show-only", "--import"]);
+            let listing = spawn_with_input(cmd.stdout(Stdio::piped()), &exported.stdout)?
+                .wait_with_output()?;
+            let missing = missing_from_listing(keys, &listing.stdout);
+            if listing.status.success() && missing.is_empty() {
+                debug!("importing {keys:?} from {keyring}");
+                let mut cmd = gpg(build_user);
+                cmd.arg("--import");
+                let status = spawn_with_input(&mut cmd, &exported.stdout)?.wait()?;
+                return helpers::check_status(&cmd, status);
+            }
+            debug!("{missing:?} not in {keyring}");
+        }
+    }
+
+    let mut cmd = gpg(build_user);
+    if let Some(keyserver) = &conf.pgp_keyserver {
+        cmd.args(["--keyserver", keyserver]);
+    }
+    helpers::run(cmd.arg("--recv-keys").args(keys))
+}
+
+/// PGP keys the user accepted to import, per package base, kept in the database directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrustedKeys(BTreeMap<String, Vec<String>>);
+
+fn trusted_keys_file(conf: &Config) -> PathBuf {
+    Path::new(&conf.db_path).join(TRUSTED_KEYS_FILE)
+}
+
+impl TrustedKeys {
+    pub fn load(conf: &Config) -> Result<Self> {
+        let path = trusted_keys_file(conf);
+        if !path.exists() {
+            return Ok(TrustedKeys::default());
+        }
+        let data = fs::read(&path)?;
+        serde_json::from_slice(&data).with_context(|| format!("Invalid trusted keys at {path:?}"))
+    }
+
+    pub fn save(&self, conf: &Config) -> Result<()> {
+        fs::create_dir_all(&conf.db_path)?;
+        let path = trusted_keys_file(conf);
+        let tmp = path.with_extension("json.part");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Whether `key` was trusted for building `pkgbase`.
+    pub fn is_trusted(&self, pkgbase: &str, key: &str) -> bool {
+        self.0
+            .get(pkgbase)
+            .is_some_and(|keys| keys.iter().any(|trusted| trusted == key))
+    }
+
+    pub fn trust(&mut self, pkgbase: &str, keys: &[String]) {
+        let trusted = self.0.entry(pkgbase.to_string()).or_default();
+        for key in keys {
+            if !trusted.contains(key) {
+                trusted.push(key.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,535 @@
+use {
+    crate::{
+        colors::{self, Theme},
+        dep_tree::{self, Conflict, DepTree, DepType, Pkg},
+        helpers, tr,
+    },
+    alpm::Alpm,
+    anyhow::{anyhow, Result},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{HashMap, HashSet},
+        io::{self, Write},
+    },
+};
+
+/// A package the transaction installs or builds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlanPkg {
+    pub name: String,
+    pub version: String,
+    /// Package base to build, for AUR packages.
+    pub base: Option<String>,
+    /// Sync database it comes from, for repo packages.
+    pub repo: Option<String>,
+    /// Why the package is part of the transaction : `target`, `depends`, `make_depends`...
+    pub reason: String,
+    /// AUR packages of the plan it needs built first.
+    pub aur_depends: Vec<String>,
+    /// Sizes in bytes, only known for repo packages.
+    pub download_size: Option<i64>,
+    pub installed_size: Option<i64>,
+}
+
+/// A package of the plan pulling in another one, as found walking the dependency trees.
+#[derive(Clone, Debug)]
+pub struct PlanDep {
+    pub package: String,
+    pub dependency: String,
+    pub dep_type: DepType,
+}
+
+/// An installed package that a package of the transaction replaces.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Replacement {
+    pub package: String,
+    pub replaces: String,
+}
+
+/// Everything a transaction is going to do, as shown before confirming it and emitted with
+/// `--print-plan --json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Plan {
+    /// Repo packages to install, targets and dependencies of AUR packages alike.
+    pub repo: Vec<PlanPkg>,
+    /// AUR packages to build, dependencies first.
+    pub aur: Vec<PlanPkg>,
+    /// Packages only needed to build others, removed afterward.
+    pub remove_after: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+    pub replacements: Vec<Replacement>,
+    /// Totals of the repo packages' sizes, in bytes.
+    pub download_size: i64,
+    pub installed_size: i64,
+    /// Packages allowed to replace the ones they conflict with, as chosen by the user.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replace: Vec<String>,
+    /// What pulls in what, to tell what is still needed once packages are left out.
+    #[serde(skip)]
+    pub deps: Vec<PlanDep>,
+}
+
+/// What to do about a conflict.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    /// Let pacman remove the conflicting package.
+    Replace,
+    /// Leave the package out of the transaction.
+    Skip,
+    Abort,
+}
+
+/// Ask on the terminal what to do about `conflict`, aborting by default like pacman.
+pub fn ask_resolution(conflict: &Conflict, can_skip: bool) -> Result<Resolution> {
+    let Theme {
+        prompt,
+        title,
+        clear,
+        ..
+    } = colors::theme();
+
+    let (replace, skip, abort) = (
+        tr!("answer-replace"),
+        tr!("answer-skip"),
+        tr!("answer-abort"),
+    );
+    let (question, choices) = if can_skip {
+        (
+            "conflict-prompt-skip",
+            format!("[{replace}/{skip}/{}]", abort.to_uppercase()),
+        )
+    } else {
+        (
+            "conflict-prompt",
+            format!("[{replace}/{}]", abort.to_uppercase()),
+        )
+    };
+
+    loop {
+        print!(
+            "{prompt}::{clear} {title}{} {choices}{clear} ",
+            tr!(
+                question,
+                package = conflict.package.as_str(),
+                other = conflict.conflicts_with.as_str()
+            )
+        );
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        let answer = input.trim().to_lowercase();
+        let Some(c) = answer.chars().next() else {
+            return Ok(Resolution::Abort);
+        };
+        if abort.starts_with(c) {
+            return Ok(Resolution::Abort);
+        } else if replace.starts_with(c) {
+            return Ok(Resolution::Replace);
+        } else if can_skip && skip.starts_with(c) {
+            return Ok(Resolution::Skip);
+        }
+        // answering in English always works, whatever the locale
+        match c {
+            'a' => return Ok(Resolution::Abort),
+            'r' => return Ok(Resolution::Replace),
+            's' if can_skip => return Ok(Resolution::Skip),
+            _ => (),
+        }
+    }
+}
+
+/// Order `roots` and their dependencies so that every package comes after the ones it depends
+/// on. Dependency cycles are broken arbitrarily.
+pub fn build_order(roots: &[String], deps: &HashMap<String, Vec<String>>) -> Vec<String> {
+    fn visit(
+        name: &str,
+        deps: &HashMap<String, Vec<String>>,
+        seen: &mut HashSet<String>,
+        order: &mut Vec<String>,
+    ) {
+        if !seen.insert(name.to_string()) {
+            return;
+        }
+        for dep in deps.get(name).into_iter().flatten() {
+            visit(dep, deps, seen, order);
+        }
+        order.push(name.to_string());
+    }
+
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    for root in roots {
+        visit(root, deps, &mut seen, &mut order);
+    }
+    order
+}
+
+/// Rank of the strongest reason for a package to be installed, lower being stronger.
+fn strength(dep_type: DepType) -> u8 {
+    match dep_type {
+        DepType::Base => 0,
+        DepType::Dep => 1,
+        DepType::OptDep => 2,
+        DepType::MakeDep | DepType::CheckDep => 3,
+    }
+}
+
+fn walk<'t, 'a>(tree: &'t DepTree<'a>, f: &mut impl FnMut(&'t DepTree<'a>)) {
+    f(tree);
+    for leave in tree.leaves().into_iter().flatten() {
+        walk(leave, f);
+    }
+}
+
+impl Plan {
+    /// The plan installing `repo_targets` and building the AUR packages of `trees`.
+    pub fn new(trees: &[DepTree], repo_targets: &[&alpm::Package], alpm: &Alpm) -> Self {
+        // every package to install, with its strongest reason, in the order they were found
+        let mut wanted: Vec<(Pkg, DepType)> = repo_targets
+            .iter()
+            .map(|pkg| (Pkg::from(*pkg), DepType::Base))
+            .collect();
+        let mut aur_deps: HashMap<String, Vec<String>> = HashMap::new();
+        let mut deps: Vec<PlanDep> = Vec::new();
+
+        for tree in trees {
+            walk(tree, &mut |node| {
+                if node.installed() {
+                    return;
+                }
+                let name = node.pkg().name();
+                for leave in node.leaves().into_iter().flatten() {
+                    let dependency = leave.pkg().name();
+                    if !leave.installed()
+                        && !deps
+                            .iter()
+                            .any(|dep| dep.package == name && dep.dependency == dependency)
+                    {
+                        deps.push(PlanDep {
+                            package: name.clone(),
+                            dependency,
+                            dep_type: leave.dep_type(),
+                        });
+                    }
+                }
+                match wanted.iter_mut().find(|(pkg, _)| pkg.name() == name) {
+                    Some((_, dep_type)) if strength(node.dep_type()) < strength(*dep_type) => {
+                        *dep_type = node.dep_type()
+                    }
+                    Some(_) => (),
+                    None => wanted.push((node.pkg().clone(), node.dep_type())),
+                }
+
+                if let (Pkg::Aur { .. }, Some(leaves)) = (node.pkg(), node.leaves()) {
+                    aur_deps.entry(name).or_default().extend(
+                        leaves
+                            .iter()
+                            .filter(|leave| matches!(leave.pkg(), Pkg::Aur { .. }))
+                            .map(|leave| leave.pkg().name()),
+                    );
+                }
+            });
+        }
+
+        let mut plan = Plan {
+            deps,
+            ..Plan::default()
+        };
+        let mut aur = HashMap::new();
+        for (pkg, dep_type) in &wanted {
+            if dep_type.is_build_only() {
+                plan.remove_after.push(pkg.name());
+            }
+            let mut planned = PlanPkg {
+                name: pkg.name(),
+                version: pkg.version(),
+                base: None,
+                repo: None,
+                reason: dep_type.as_str().to_string(),
+                aur_depends: Vec::new(),
+                download_size: None,
+                installed_size: None,
+            };
+            match pkg {
+                Pkg::Pacman { pkg } => {
+                    planned.repo = pkg.db().map(|db| db.name().to_string());
+                    planned.download_size = Some(pkg.download_size());
+                    planned.installed_size = Some(pkg.isize());
+                    plan.download_size += pkg.download_size();
+                    plan.installed_size += pkg.isize();
+                    plan.repo.push(planned);
+                }
+                Pkg::Aur { pkg } => {
+                    planned.base = Some(pkg.package_base.clone());
+                    planned.aur_depends = aur_deps.get(&planned.name).cloned().unwrap_or_default();
+                    aur.insert(planned.name.clone(), planned);
+                }
+            }
+        }
+
+        let roots = trees
+            .iter()
+            .map(|tree| tree.pkg().name())
+            .collect::<Vec<_>>();
+        plan.aur = build_order(&roots, &aur_deps)
+            .into_iter()
+            .filter_map(|name| aur.remove(&name))
+            .collect();
+
+        let pkgs = wanted
+            .iter()
+            .map(|(pkg, _)| pkg.clone())
+            .collect::<Vec<_>>();
+        plan.conflicts = dep_tree::find_conflicts(&pkgs, alpm);
+
+        let local_pkgs = alpm.localdb().pkgs();
+        for (pkg, _) in &wanted {
+            let name = pkg.name();
+            for replaced in pkg.replaces() {
+                if let Some(installed) = local_pkgs.find_satisfier(replaced.as_str()) {
+                    if installed.name() != name {
+                        plan.replacements.push(Replacement {
+                            package: name.clone(),
+                            replaces: installed.name().to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        plan
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.repo.is_empty() && self.aur.is_empty()
+    }
+
+    fn is_target(&self, name: &str) -> bool {
+        self.repo
+            .iter()
+            .chain(&self.aur)
+            .any(|pkg| pkg.name == name && pkg.reason == "target")
+    }
+
+    /// Whether `name` was allowed to replace the packages it conflicts with.
+    pub fn replaces(&self, name: &str) -> bool {
+        self.replace.iter().any(|replace| replace == name)
+    }
+
+    /// The packages still needed once `skipped` are left out, along with the strongest reason
+    /// each of them is pulled in for. Targets, and packages nothing in the plan pulls in, are
+    /// needed for their own sake.
+    fn needed(&self, skipped: &[String]) -> HashMap<String, Option<DepType>> {
+        let mut needed = HashMap::new();
+        let mut queue = Vec::new();
+        for pkg in self.repo.iter().chain(&self.aur) {
+            let pulled_in = self.deps.iter().any(|dep| dep.dependency == pkg.name);
+            if !skipped.contains(&pkg.name) && (pkg.reason == "target" || !pulled_in) {
+                needed.insert(pkg.name.clone(), None);
+                queue.push(pkg.name.clone());
+            }
+        }
+
+        while let Some(name) = queue.pop() {
+            for dep in self.deps.iter().filter(|dep| dep.package == name) {
+                if skipped.contains(&dep.dependency) {
+                    continue;
+                }
+                match needed.get_mut(&dep.dependency) {
+                    None => {
+                        needed.insert(dep.dependency.clone(), Some(dep.dep_type));
+                        queue.push(dep.dependency.clone());
+                    }
+                    Some(Some(dep_type)) if strength(dep.dep_type) < strength(*dep_type) => {
+                        *dep_type = dep.dep_type
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+        needed
+    }
+
+    /// The packages left out along with `skipped`, them included.
+    fn dropped(&self, skipped: &[String]) -> HashSet<String> {
+        let needed = self.needed(skipped);
+        self.repo
+            .iter()
+            .chain(&self.aur)
+            .map(|pkg| pkg.name.clone())
+            .filter(|name| !needed.contains_key(name))
+            .collect()
+    }
+
+    /// Leave `skipped` out, along with the packages only they pulled in.
+    fn leave_out(&mut self, skipped: &[String]) {
+        let needed = self.needed(skipped);
+        let dropped = self.dropped(skipped);
+
+        for pkg in self.repo.iter().filter(|pkg| dropped.contains(&pkg.name)) {
+            self.download_size -= pkg.download_size.unwrap_or(0);
+            self.installed_size -= pkg.installed_size.unwrap_or(0);
+        }
+        self.repo.retain(|pkg| !dropped.contains(&pkg.name));
+        self.aur.retain(|pkg| !dropped.contains(&pkg.name));
+
+        // what is left may now only be needed to build others
+        for pkg in self.repo.iter_mut().chain(self.aur.iter_mut()) {
+            if let Some(Some(dep_type)) = needed.get(&pkg.name) {
+                if pkg.reason != "target" {
+                    pkg.reason = dep_type.as_str().to_string();
+                }
+            }
+        }
+        let build_only = |name: &String| matches!(needed.get(name), Some(Some(dep_type)) if dep_type.is_build_only());
+        self.remove_after.retain(|name| build_only(name));
+        for pkg in self.repo.iter().chain(&self.aur) {
+            if build_only(&pkg.name) && !self.remove_after.contains(&pkg.name) {
+                self.remove_after.push(pkg.name.clone());
+            }
+        }
+
+        self.conflicts
+            .retain(|c| !dropped.contains(&c.package) && !dropped.contains(&c.conflicts_with));
+        self.replacements
+            .retain(|replacement| !dropped.contains(&replacement.package));
+        self.replace.retain(|name| !dropped.contains(name));
+        self.deps
+            .retain(|dep| !dropped.contains(&dep.package) && !dropped.contains(&dep.dependency));
+    }
+
+    /// Ask what to do about each conflict, through `choose` which gets the conflict and
+    /// whether skipping the package is an option (only targets can be left out). Skipped
+    /// packages are removed from the plan along with the dependencies only they need, and the
+    /// plan is abandoned on the first abort.
+    pub fn resolve_conflicts(
+        &mut self,
+        mut choose: impl FnMut(&Conflict, bool) -> Result<Resolution>,
+    ) -> Result<()> {
+        let mut skipped: Vec<String> = Vec::new();
+        // packages left out so far, skipped or only needed by skipped ones
+        let mut dropped = HashSet::new();
+        for conflict in &self.conflicts {
+            if dropped.contains(&conflict.package) || dropped.contains(&conflict.conflicts_with) {
+                continue;
+            }
+            match choose(conflict, self.is_target(&conflict.package))? {
+                Resolution::Replace => self.replace.push(conflict.package.clone()),
+                Resolution::Skip => {
+                    skipped.push(conflict.package.clone());
+                    dropped = self.dropped(&skipped);
+                }
+                Resolution::Abort => {
+                    return Err(anyhow!(tr!(
+                        "conflict-aborted",
+                        package = conflict.package.as_str(),
+                        other = conflict.conflicts_with.as_str()
+                    )))
+                }
+            }
+        }
+
+        if !skipped.is_empty() {
+            self.leave_out(&skipped);
+        }
+
+        Ok(())
+    }
+
+    /// Print the plan, grouped the way it is going to be carried out.
+    pub fn print(&self) {
+        let Theme {
+            header,
+            title,
+            name,
+            version,
+            dim,
+            number,
+            warning,
+            clear,
+            ..
+        } = colors::theme();
+
+        let reason = |pkg: &PlanPkg| match pkg.reason.as_str() {
+            "target" => String::new(),
+            reason => format!(" {dim}({}){clear}", tr!(&format!("reason-{reason}"))),
+        };
+
+        if !self.repo.is_empty() {
+            println!(
+                "{header}::{clear} {title}{}{clear}",
+                tr!("plan-repo", count = self.repo.len())
+            );
+            for pkg in &self.repo {
+                println!(
+                    "   {name}{}{clear} {version}{}{clear}{}",
+                    pkg.name,
+                    pkg.version,
+                    reason(pkg)
+                );
+            }
+        }
+
+        if !self.aur.is_empty() {
+            println!(
+                "{header}::{clear} {title}{}{clear}",
+                tr!("plan-aur", count = self.aur.len())
+            );
+            for (i, pkg) in self.aur.iter().enumerate() {
+                println!(
+                    "   {number}{}{clear} {name}{}{clear} {version}{}{clear}{}",
+                    i + 1,
+                    pkg.name,
+                    pkg.version,
+                    reason(pkg)
+                );
+            }
+        }
+
+        if !self.remove_after.is_empty() {
+            println!(
+                "{header}::{clear} {title}{}{clear}",
+                tr!("plan-remove-after", count = self.remove_after.len())
+            );
+            println!("   {}", self.remove_after.join(" "));
+        }
+
+        for conflict in &self.conflicts {
+            let id = if conflict.installed {
+                "plan-conflict-installed"
+            } else {
+                "plan-conflict-planned"
+            };
+            println!(
+                "{warning}::{clear} {}",
+                tr!(
+                    id,
+                    package = conflict.package.as_str(),
+                    other = conflict.conflicts_with.as_str()
+                )
+            );
+        }
+        for replacement in &self.replacements {
+            println!(
+                "{warning}::{clear} {}",
+                tr!(
+                    "plan-replaces",
+                    package = replacement.package.as_str(),
+                    other = replacement.replaces.as_str()
+                )
+            );
+        }
+
+        if !self.repo.is_empty() {
+            println!(
+                "{header}::{clear} {}",
+                tr!(
+                    "plan-sizes",
+                    download = helpers::human_size(self.download_size),
+                    installed = helpers::human_size(self.installed_size)
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use {
+    crate::{config::Config, helpers},
+    anyhow::{anyhow, Result},
+    log::{debug, info, warn},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        process::Command,
+        time::Duration,
+    },
+    tokio::task::JoinHandle,
+    users::{get_current_uid, get_user_by_name},
+};
+
+/// How often cached credentials get refreshed while a transaction is running.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(60);
+
+const ROOT_BUILD_ERR: &str = "Refusing to build packages as root, please run rah as a regular user (it will ask for privileges when installing packages) or set build_user in the config";
+
+/// Runs the few commands that do need root (installing and removing packages) through the
+/// configured escalation command, so that the rest of rah (and makepkg) runs unprivileged.
+pub struct Escalation {
+    cmd: Vec<String>,
+    keep_alive: Option<JoinHandle<()>>,
+}
+
+impl Escalation {
+    pub fn new(conf: &Config) -> Self {
+        Escalation {
+            cmd: conf
+                .escalation_cmd
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect(),
+            keep_alive: None,
+        }
+    }
+
+    fn is_root() -> bool {
+        get_current_uid() == 0
+    }
+
+    fn program(&self) -> &str {
+        self.cmd
+            .first()
+            .and_then(|cmd| cmd.rsplit('/').next())
+            .unwrap_or_default()
+    }
+
+    /// Prompt for credentials once, upfront, and keep them cached until the transaction ends.
+    pub fn authenticate(&mut self) -> Result<()> {
+        if Self::is_root() || self.keep_alive.is_some() {
+            return Ok(());
+        }
+
+        if self.cmd.is_empty() {
+            return Err(anyhow!(
+                "No escalation command configured, set escalation_cmd in the config"
+            ));
+        }
+
+        // the arguments refreshing cached credentials without prompting, if the command
+        // supports caching at all
+        let (validate, refresh): (&[&str], &[&str]) = match self.program() {
+            "sudo" => (&["-v"], &["-n", "-v"]),
+            "doas" => (&["true"], &["-n", "true"]),
+            other => {
+                warn!("{other} doesn't cache credentials, you may be prompted several times");
+                return Ok(());
+            }
+        };
+
+        debug!("authenticating through {:?}", self.cmd);
+        helpers::run(
+            Command::new(&self.cmd[0])
+                .args(&self.cmd[1..])
+                .args(validate),
+        )?;
+
+        let cmd = self.cmd.clone();
+        let refresh = refresh.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        self.keep_alive = Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(KEEP_ALIVE_INTERVAL).await;
+                let _ = tokio::process::Command::new(&cmd[0])
+                    .args(&cmd[1..])
+                    .args(&refresh)
+                    .status()
+                    .await;
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// `program`, wrapped in the escalation command unless we already are root.
+    pub fn command(&self, program: &str) -> Command {
+        if Self::is_root() || self.cmd.is_empty() {
+            return Command::new(program);
+        }
+
+        let mut cmd = Command::new(&self.cmd[0]);
+        cmd.args(&self.cmd[1..]).arg(program);
+        cmd
+    }
+}
+
+impl Drop for Escalation {
+    fn drop(&mut self) {
+        if let Some(keep_alive) = self.keep_alive.take() {
+            keep_alive.abort();
+        }
+    }
+}
+
+/// Unprivileged system user running git and makepkg when rah itself runs as root (e.g. from
+/// automation).
+#[derive(Clone)]
+pub struct BuildUser {
+    name: String,
+    home: PathBuf,
+}
+
+impl BuildUser {
+    /// The configured build user when running as root, created on the fly if it doesn't exist
+    /// yet. Returns `None` when not running as root, builds running as the current user then.
+    pub fn setup(conf: &Config) -> Result<Option<BuildUser>> {
+        if !Escalation::is_root() {
+            return Ok(None);
+        }
+
+        let Some(name) = &conf.build_user else {
+            return Err(anyhow!(ROOT_BUILD_ERR));
+        };
+
+        let home = Path::new(&conf.cache_path).to_path_buf();
+        fs::create_dir_all(&home)?;
+
+        if get_user_by_name(name).is_none() {
+            info!("Creating build user {name}...");
+            helpers::run(
+                Command::new("useradd")
+                    .args([
+                        "--system",
+                        "--no-create-home",
+                        "--shell",
+                        "/usr/bin/nologin",
+                    ])
+                    .args(["--comment", "rah build user", "--home-dir"])
+                    .arg(&home)
+                    .arg(name),
+            )?;
+        }
+
+        Ok(Some(BuildUser {
+            name: name.clone(),
+            home,
+        }))
+    }
+
+    /// Hand `path` (recursively) over to the build user.
+    pub fn own(&self, path: &Path) -> Result<()> {
+        fs::create_dir_all(path)?;
+        helpers::run(
+            Command::new("chown")
+                .arg("--recursive")
+                .arg(format!("{}:", self.name))
+                .arg(path),
+        )
+    }
+
+    /// `program`, run as the build user. runuser drops root's supplementary groups too, unlike
+    /// a bare setuid.
+    pub fn command(&self, program: &str) -> Command {
+        let mut cmd = Command::new("runuser");
+        cmd.args(["-u", &self.name, "--", program])
+            .env("HOME", &self.home);
+        cmd
+    }
+}
+
+/// Command running `program` as whoever should do the builds.
+pub fn build_command(build_user: Option<&BuildUser>, program: &str) -> Command {
+    match build_user {
+        Some(build_user) => build_user.command(program),
+        None => Command::new(program),
+    }
+}
+
+/// makepkg refuses to run as root, and AUR build scripts shouldn't get root anyway.
+pub fn check_build_user(build_user: Option<&BuildUser>) -> Result<()> {
+    if Escalation::is_root() && build_user.is_none() {
+        return Err(anyhow!(ROOT_BUILD_ERR));
+    }
+
+    Ok(())
+}
@@ -1,4 +1,179 @@
-pub fn search(packages: Vec<&str>) {
-    println!("Xearching for {}", packages.join(", "));
-    todo!()
+use {
+    crate::{
+        colors::{self, Theme},
+        config::Config,
+        helpers, output, tr,
+    },
+    alpm::{Package, PackageReason},
+    anyhow::{anyhow, Result},
+    log::trace,
+    serde::Serialize,
+};
+
+/// An installed package, as emitted with `--json`.
+#[derive(Serialize)]
+pub struct LocalRecord {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub explicit: bool,
+    pub installed_size: i64,
+    pub depends: Vec<String>,
+    pub opt_depends: Vec<String>,
+    pub provides: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub replaces: Vec<String>,
+    pub required_by: Vec<String>,
+}
+
+impl LocalRecord {
+    pub fn new(pkg: &Package) -> Self {
+        LocalRecord {
+            name: pkg.name().to_string(),
+            version: pkg.version().to_string(),
+            description: pkg.desc().map(|desc| desc.to_string()),
+            explicit: pkg.reason() == PackageReason::Explicit,
+            installed_size: pkg.isize(),
+            depends: pkg.depends().iter().map(|dep| dep.to_string()).collect(),
+            opt_depends: pkg.optdepends().iter().map(|dep| dep.to_string()).collect(),
+            provides: pkg.provides().iter().map(|dep| dep.to_string()).collect(),
+            conflicts: pkg.conflicts().iter().map(|dep| dep.to_string()).collect(),
+            replaces: pkg.replaces().iter().map(|dep| dep.to_string()).collect(),
+            required_by: pkg.required_by().into_iter().collect(),
+        }
+    }
+}
+
+pub fn search(packages: Vec<&str>, conf: &Config) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        name,
+        version,
+        dim,
+        clear,
+        ..
+    } = colors::theme();
+
+    trace!("searching the local db for {packages:?}");
+
+    let alpm = helpers::alpm(conf)?;
+    let needles = packages
+        .iter()
+        .map(|needle| needle.to_lowercase())
+        .collect::<Vec<_>>();
+    // every term has to match, like pacman -Qs
+    let hits = alpm
+        .localdb()
+        .pkgs()
+        .iter()
+        .filter(|pkg| {
+            let name = pkg.name().to_lowercase();
+            let desc = pkg.desc().unwrap_or_default().to_lowercase();
+            needles
+                .iter()
+                .all(|needle| name.contains(needle) || desc.contains(needle))
+        })
+        .collect::<Vec<_>>();
+
+    if output::is_custom(conf, "local_search") {
+        let records = hits
+            .iter()
+            .map(|pkg| LocalRecord::new(pkg))
+            .collect::<Vec<_>>();
+        return output::emit(conf, "local_search", &records);
+    }
+
+    println!(
+        "{header}::{clear} {title}{}{clear}",
+        tr!("found-packages", count = hits.len())
+    );
+
+    for pkg in hits {
+        println!(
+            "{name}{}{clear} {version}{}{clear}\n    {}",
+            pkg.name(),
+            pkg.version(),
+            pkg.desc()
+                .map(|desc| desc.to_string())
+                .unwrap_or(format!("{dim}{}{clear}", tr!("no-description")))
+        );
+    }
+
+    Ok(())
+}
+
+pub fn info(packages: Vec<&str>, conf: &Config) -> Result<()> {
+    let Theme { dim, clear, .. } = colors::theme();
+
+    let alpm = helpers::alpm(conf)?;
+    let localdb = alpm.localdb();
+
+    let mut hits = Vec::new();
+    for name in &packages {
+        match localdb.pkg(*name) {
+            Ok(pkg) => hits.push(pkg),
+            Err(_) => return Err(anyhow!(tr!("package-not-installed", name = *name))),
+        }
+    }
+
+    if output::is_custom(conf, "local_info") {
+        let records = hits
+            .iter()
+            .map(|pkg| LocalRecord::new(pkg))
+            .collect::<Vec<_>>();
+        return output::emit(conf, "local_info", &records);
+    }
+
+    let list = |values: Vec<String>, none: &str| {
+        if values.is_empty() {
+            format!("{dim}{}{clear}", tr!(none))
+        } else {
+            values.join(", ")
+        }
+    };
+
+    for pkg in hits {
+        let record = LocalRecord::new(pkg);
+        helpers::print_fields(&[
+            (tr!("info-name"), record.name),
+            (tr!("info-version"), record.version),
+            (
+                tr!("info-description"),
+                record
+                    .description
+                    .unwrap_or(format!("{dim}{}{clear}", tr!("no-description"))),
+            ),
+            (
+                tr!("info-install-reason"),
+                if record.explicit {
+                    tr!("explicitly-installed")
+                } else {
+                    tr!("installed-as-dep")
+                },
+            ),
+            (
+                tr!("info-installed-size"),
+                format!("{:.2} KiB", record.installed_size as f64 / 1024.0),
+            ),
+            (tr!("info-provides"), list(record.provides, "no-provides")),
+            (tr!("info-depends"), list(record.depends, "no-depends")),
+            (
+                tr!("info-opt-depends"),
+                list(record.opt_depends, "no-opt-depends"),
+            ),
+            (
+                tr!("info-required-by"),
+                list(record.required_by, "not-required"),
+            ),
+            (
+                tr!("info-conflicts"),
+                list(record.conflicts, "no-conflicts"),
+            ),
+            (tr!("info-replaces"), list(record.replaces, "no-replaces")),
+        ]);
+        println!()
+    }
+
+    Ok(())
 }
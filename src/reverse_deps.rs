@@ -0,0 +1,281 @@
+use {
+    crate::{
+        aur::Aur,
+        colors::{self, Theme},
+        config::Config,
+        dep_tree::{parse_dependency, satisfies, DepType},
+        helpers, output, tr,
+    },
+    anyhow::{anyhow, Result},
+    futures::{stream, StreamExt},
+    log::warn,
+    raur::SearchBy,
+    serde::Serialize,
+    std::collections::{HashSet, VecDeque},
+};
+
+/// A package depending on the queried one, directly or through other packages.
+#[derive(Debug, Serialize)]
+pub struct Dependent {
+    /// The queried package.
+    pub target: String,
+    pub name: String,
+    pub version: String,
+    /// The package it depends on, either the queried one or another dependent.
+    pub requires: String,
+    /// `depends`, `make_depends`, `opt_depends` or `check_depends`.
+    pub reason: &'static str,
+    /// 1 for direct dependents.
+    pub depth: usize,
+    pub installed: bool,
+}
+
+/// What a package is known by : its name, version and provides.
+struct Provider {
+    name: String,
+    version: String,
+    provides: Vec<String>,
+}
+
+/// A package that may depend on others, with every kind of dependency it has.
+struct Candidate {
+    name: String,
+    version: String,
+    installed: bool,
+    deps: Vec<(String, DepType)>,
+}
+
+/// Optional dependencies come with a description, e.g. "foo: for bar support".
+fn strip_description(dep: &str) -> String {
+    dep.split(": ").next().unwrap_or(dep).trim().to_string()
+}
+
+fn aur_deps(pkg: &raur::Package) -> Vec<(String, DepType)> {
+    let mut deps = Vec::new();
+    for (list, dep_type) in [
+        (&pkg.depends, DepType::Dep),
+        (&pkg.make_depends, DepType::MakeDep),
+        (&pkg.check_depends, DepType::CheckDep),
+        (&pkg.opt_depends, DepType::OptDep),
+    ] {
+        deps.extend(list.iter().map(|dep| (strip_description(dep), dep_type)));
+    }
+    deps
+}
+
+/// The packages depending on `target`, breadth first. Installed packages are followed
+/// transitively, through the dependencies pacman knows about as well as the make and check
+/// dependencies of AUR packages, which it doesn't keep track of. Packages of the AUR that
+/// aren't installed are only listed when they depend on `target` directly.
+pub async fn dependents(target: &str, conf: &Config) -> Result<Vec<Dependent>> {
+    let alpm = helpers::alpm(conf)?;
+    let localdb = alpm.localdb();
+    let aur = Aur::new(conf, false).await?;
+
+    let provider = |pkg: &alpm::Package| Provider {
+        name: pkg.name().to_string(),
+        version: pkg.version().to_string(),
+        provides: pkg.provides().iter().map(|dep| dep.to_string()).collect(),
+    };
+    let target = match localdb.pkg(target) {
+        Ok(pkg) => provider(pkg),
+        Err(_) => match helpers::find_sync_pkg(&alpm, target) {
+            Some(pkg) => provider(pkg),
+            None => {
+                let pkg = aur
+                    .info(&[target])
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        anyhow!(tr!("packages-not-found", count = 1, packages = target))
+                    })?;
+                Provider {
+                    name: pkg.name,
+                    version: pkg.version,
+                    provides: pkg.provides,
+                }
+            }
+        },
+    };
+
+    // pacman only knows the runtime dependencies of installed packages, the AUR also knows
+    // the build ones of those that came from it
+    let foreign = localdb
+        .pkgs()
+        .iter()
+        .filter(|pkg| helpers::find_sync_pkg(&alpm, pkg.name()).is_none())
+        .map(|pkg| pkg.name().to_string())
+        .collect::<Vec<_>>();
+    let foreign_info = match aur.info(&foreign).await {
+        Ok(info) => info,
+        Err(err) => {
+            warn!("Could not get the build dependencies of installed AUR packages, they are left out : {err}");
+            Vec::new()
+        }
+    };
+
+    let mut candidates = localdb
+        .pkgs()
+        .iter()
+        .map(|pkg| {
+            let mut deps = pkg
+                .depends()
+                .iter()
+                .map(|dep| (dep.to_string(), DepType::Dep))
+                .chain(
+                    pkg.optdepends()
+                        .iter()
+                        .map(|dep| (strip_description(&dep.to_string()), DepType::OptDep)),
+                )
+                .collect::<Vec<_>>();
+            if let Some(info) = foreign_info.iter().find(|info| info.name == pkg.name()) {
+                deps.extend(
+                    aur_deps(info)
+                        .into_iter()
+                        .filter(|(_, dep_type)| dep_type.is_build_only()),
+                );
+            }
+            Candidate {
+                name: pkg.name().to_string(),
+                version: pkg.version().to_string(),
+                installed: true,
+                deps,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // the AUR is searched by dependency name, provides included
+    let mut aur_names = HashSet::new();
+    let mut search_err = None;
+    let names = std::iter::once(target.name.clone()).chain(
+        target
+            .provides
+            .iter()
+            .map(|provide| parse_dependency(provide).0.to_string()),
+    );
+    let searches = names
+        .flat_map(|name| {
+            [
+                SearchBy::Depends,
+                SearchBy::MakeDepends,
+                SearchBy::CheckDepends,
+                SearchBy::OptDepends,
+            ]
+            .map(|by| (name.clone(), by))
+        })
+        .collect::<Vec<_>>();
+    // in order, so that the dependents are listed the same way every time
+    let aur = &aur;
+    let results = stream::iter(searches)
+        .map(|(name, by)| async move { aur.search_by(&name, by).await })
+        .buffered(conf.aur_concurrency)
+        .collect::<Vec<_>>()
+        .await;
+    for res in results {
+        let found = res.unwrap_or_else(|err| {
+            search_err = Some(err);
+            Vec::new()
+        });
+        for pkg in found {
+            if localdb.pkg(pkg.name.as_str()).is_err() && aur_names.insert(pkg.name.clone()) {
+                candidates.push(Candidate {
+                    name: pkg.name.clone(),
+                    version: pkg.version.clone(),
+                    installed: false,
+                    deps: aur_deps(&pkg),
+                });
+            }
+        }
+    }
+
+    let target_name = target.name.clone();
+    if let Some(err) = search_err {
+        warn!("Could not search the AUR for packages depending on {target_name}, some may be missing : {err}");
+    }
+
+    let mut dependents = Vec::new();
+    let mut seen = HashSet::from([target.name.clone()]);
+    let mut queue = VecDeque::from([(target, 0)]);
+    while let Some((required, depth)) = queue.pop_front() {
+        for candidate in &candidates {
+            // AUR packages that aren't installed don't get anything else pulled in
+            if (!candidate.installed && depth > 0) || seen.contains(&candidate.name) {
+                continue;
+            }
+            let Some((_, dep_type)) = candidate.deps.iter().find(|(dep, _)| {
+                satisfies(&required.name, &required.version, &required.provides, dep)
+            }) else {
+                continue;
+            };
+
+            seen.insert(candidate.name.clone());
+            dependents.push(Dependent {
+                target: target_name.clone(),
+                name: candidate.name.clone(),
+                version: candidate.version.clone(),
+                requires: required.name.clone(),
+                reason: dep_type.as_str(),
+                depth: depth + 1,
+                installed: candidate.installed,
+            });
+            if candidate.installed {
+                let pkg = localdb.pkg(candidate.name.as_str())?;
+                queue.push_back((provider(pkg), depth + 1));
+            }
+        }
+    }
+
+    Ok(dependents)
+}
+
+/// `-Qi --required-by` and `rah whyneeded` : print what depends on each of `packages`.
+pub async fn print(packages: Vec<&str>, conf: &Config) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        name,
+        version,
+        installed,
+        dim,
+        clear,
+        ..
+    } = colors::theme();
+
+    let mut records = Vec::new();
+    for package in &packages {
+        let found = dependents(package, conf).await?;
+        if output::is_custom(conf, "reverse_deps") {
+            records.extend(found);
+            continue;
+        }
+
+        println!(
+            "{header}::{clear} {title}{}{clear}",
+            tr!(
+                "required-by-header",
+                count = found.len(),
+                package = *package
+            )
+        );
+        for dependent in found {
+            let indent = "  ".repeat(dependent.depth);
+            let reason = tr!(&format!("reason-{}", dependent.reason));
+            let marker = if dependent.installed {
+                format!("{installed}[{}]{clear}", tr!("graph-installed"))
+            } else {
+                format!("{dim}[{}]{clear}", tr!("graph-aur"))
+            };
+            println!(
+                " {indent}{name}{}{clear} {version}{}{clear} {dim}← {} ({reason}){clear} {marker}",
+                dependent.name, dependent.version, dependent.requires
+            );
+        }
+    }
+
+    if output::is_custom(conf, "reverse_deps") {
+        return output::emit(conf, "reverse_deps", &records);
+    }
+
+    Ok(())
+}
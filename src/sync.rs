@@ -1,117 +1,847 @@
 use {
     crate::{
-        colors::*,
+        aur::Aur,
+        aur_scheduler::AurScheduler,
+        colors::{self, Theme},
+        config::Config,
+        dep_graph,
         dep_tree::{DepTree, Pkg},
+        diagnosis, helpers,
+        local_repo::LocalRepo,
+        makepkg, output, pgp,
+        plan::{self, Plan, PlanPkg, Resolution},
+        privilege::{BuildUser, Escalation},
+        tr,
+        transaction::{self, Built, Recovery, Transaction, Unfinished},
     },
-    alpm::Alpm,
+    alpm::Db,
     anyhow::{anyhow, Result},
     chrono::{TimeZone, Utc},
-    log::{error, trace},
-    raur::Raur,
+    futures::{stream, StreamExt},
+    log::{error, info, trace},
+    raur::SearchBy,
+    serde::Serialize,
+    std::{
+        collections::HashSet,
+        fs,
+        path::{Path, PathBuf},
+    },
+    tokio::task,
 };
 
-const AUR_URL: &str = "https://aur.archlinux.org/";
+/// How a transaction gets carried out.
+#[derive(Default)]
+pub struct SyncOptions {
+    /// Refresh pacman's databases and the AUR metadata first.
+    pub refresh: bool,
+    /// Only show what would be done.
+    pub print_plan: bool,
+}
+
+pub async fn sync(packages: Vec<&str>, conf: &Config, opts: &SyncOptions) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        clear,
+        ..
+    } = colors::theme();
 
-pub async fn sync(packages: Vec<&str>) -> Result<()> {
-    let raur = raur::Handle::new();
-    let alpm = Alpm::new("/", "/var/lib/pacman/")?;
+    let noconfirm = conf.pacman_flags.iter().any(|flag| flag == "--noconfirm");
+    // the saved transaction can't be resumed once replaced, so the user gets to choose
+    if !opts.print_plan && Transaction::load(conf)?.is_some() {
+        if noconfirm {
+            return Err(anyhow!(tr!("unfinished-noconfirm")));
+        }
+        match transaction::ask_unfinished()? {
+            Unfinished::Resume => return resume(conf).await,
+            Unfinished::Discard => Transaction::clear(conf)?,
+            Unfinished::Abort => return Err(anyhow!(tr!("unfinished-aborted"))),
+        }
+    }
 
-    let hits = raur.info(&packages).await?;
+    let mut escalation = Escalation::new(conf);
 
-    if hits.len() != packages.len() {
-        let mut err_msg = format!("Package(s) not found : ");
-        let hit_names = hits.iter().map(|pkg| pkg.name.clone()).collect::<Vec<_>>();
-        for package in packages.as_slice() {
-            if !(hit_names.contains(&package.to_string())) {
-                err_msg.push_str(package);
-                err_msg.push(' ');
+    // refreshing is pacman's business, and has to happen before looking packages up
+    if opts.refresh && !opts.print_plan {
+        escalation.authenticate()?;
+        let mut cmd = escalation.command("pacman");
+        helpers::run(cmd.arg("-Sy"))?;
+    }
+
+    let alpm = helpers::alpm(conf)?;
+    let (repo_packages, packages) = helpers::split_pacman_aur(&alpm, &packages);
+    let repo_targets = repo_packages
+        .iter()
+        .filter_map(|name| helpers::find_sync_pkg(&alpm, name))
+        .collect::<Vec<_>>();
+    if repo_targets.len() != repo_packages.len() {
+        let missing = repo_packages
+            .iter()
+            .filter(|name| helpers::find_sync_pkg(&alpm, name).is_none())
+            .copied()
+            .collect::<Vec<_>>();
+        return Err(anyhow!(tr!(
+            "packages-not-found",
+            count = missing.len(),
+            packages = missing.join(" ")
+        )));
+    }
+
+    let aur = Aur::new(conf, opts.refresh).await?;
+    let hits = aur_targets(&aur, &packages).await?;
+
+    let formated_hits = hits.iter().cloned().map(Pkg::from).collect::<Vec<Pkg>>();
+    let mut scheduler = AurScheduler::new(&aur, conf.aur_concurrency);
+    let trees = DepTree::build_all(&formated_hits, &alpm, &mut scheduler).await?;
+    let mut plan = Plan::new(&trees, &repo_targets, &alpm);
+
+    if opts.print_plan {
+        if output::is_custom(conf, "plan") {
+            return output::emit(conf, "plan", &[&plan]);
+        }
+        plan.print();
+        return Ok(());
+    }
+
+    plan.print();
+    if !plan.conflicts.is_empty() {
+        // without anyone to ask, conflicts abort the transaction like they would in pacman
+        if noconfirm {
+            plan.resolve_conflicts(|_, _| Ok(Resolution::Abort))?;
+        } else {
+            plan.resolve_conflicts(plan::ask_resolution)?;
+        }
+    }
+    if plan.is_empty() {
+        println!("{header}::{clear} {title}{}{clear}", tr!("nothing-to-do"));
+        return Ok(());
+    }
+    if !noconfirm && !helpers::confirm(&tr!("plan-proceed"), true)? {
+        return Ok(());
+    }
+
+    let mut transaction = Transaction::new(plan, conf.pacman_flags.clone());
+    transaction.save(conf)?;
+    execute(conf, &mut transaction).await
+}
+
+/// `rah --resume` : carry on with the transaction that failed or was aborted.
+pub async fn resume(conf: &Config) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        clear,
+        ..
+    } = colors::theme();
+
+    let Some(mut transaction) = Transaction::load(conf)? else {
+        println!("{header}::{clear} {title}{}{clear}", tr!("no-transaction"));
+        return Ok(());
+    };
+    println!(
+        "{header}::{clear} {title}{}{clear}",
+        tr!(
+            "resuming",
+            built = transaction.built.len(),
+            pending = transaction.pending().len()
+        )
+    );
+    execute(conf, &mut transaction).await
+}
+
+/// The pacman flags to install `names` with, telling pacman to remove what they conflict with
+/// when the user chose to replace it.
+fn flags_for(transaction: &Transaction, names: &[&str]) -> Vec<String> {
+    let mut flags = transaction.pacman_flags.clone();
+    if names.iter().any(|name| transaction.plan.replaces(name)) {
+        flags.push(helpers::ASK_REMOVE_CONFLICTS.to_string());
+    }
+    flags
+}
+
+/// Import `keys` for building `pkgbase`, and remember them as trusted for it.
+fn import_keys(
+    conf: &Config,
+    pkgbase: &str,
+    keys: &[String],
+    build_user: Option<&BuildUser>,
+) -> Result<()> {
+    pgp::import(keys, conf, build_user)?;
+    let mut trusted = pgp::TrustedKeys::load(conf)?;
+    trusted.trust(pkgbase, keys);
+    trusted.save(conf)
+}
+
+/// Look the PGP keys the sources of `pkgbase` are signed with up in the keyring before
+/// building, offering to import the missing ones. Keys trusted for `pkgbase` before get
+/// imported again without asking.
+fn check_keys(
+    conf: &Config,
+    dir: &Path,
+    pkgbase: &str,
+    build_user: Option<&BuildUser>,
+    noconfirm: bool,
+) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        name,
+        dim,
+        clear,
+        ..
+    } = colors::theme();
+
+    let missing = pgp::missing_keys(&pgp::srcinfo_keys(dir)?, build_user)?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let trusted = pgp::TrustedKeys::load(conf)?;
+    if !missing.iter().all(|key| trusted.is_trusted(pkgbase, key)) {
+        println!(
+            "{header}::{clear} {title}{}{clear}",
+            tr!("missing-keys", count = missing.len(), pkgbase = pkgbase)
+        );
+        for key in &missing {
+            let uid = pgp::uid(key, conf, build_user).unwrap_or_else(|| tr!("unknown-uid"));
+            println!("   {name}{key}{clear} {dim}{uid}{clear}");
+        }
+        // without the keys the build fails, and gets diagnosed as such
+        if noconfirm || !helpers::confirm(&tr!("import-keys-pkgbase"), true)? {
+            return Ok(());
+        }
+    }
+
+    if let Err(err) = import_keys(conf, pkgbase, &missing, build_user) {
+        error!("{err}");
+    }
+    Ok(())
+}
+
+/// Build `builds` (package bases along with their clone), `build_jobs` of them at most at the
+/// same time, each line of their output starting with the package base when several run.
+/// The results come in the order of `builds`.
+async fn build_batch(
+    conf: &Config,
+    builds: &[(String, PathBuf)],
+    pkgdest: &Path,
+    build_user: Option<&BuildUser>,
+) -> Vec<Result<Vec<PathBuf>>> {
+    let Theme {
+        header,
+        title,
+        name,
+        clear,
+        ..
+    } = colors::theme();
+
+    let jobs = conf.build_jobs.max(1);
+    let parallel = jobs > 1 && builds.len() > 1;
+    stream::iter(builds)
+        .map(|(pkgbase, dir)| {
+            let (dir, pkgdest) = (dir.clone(), pkgdest.to_path_buf());
+            let log = makepkg::log_path(conf, pkgbase);
+            let build_user = build_user.cloned();
+            let prefix = parallel.then(|| format!("{name}{pkgbase}{clear} | "));
+            async move {
+                println!(
+                    "{header}::{clear} {title}{}{clear}",
+                    tr!("building", pkgbase = pkgbase.as_str())
+                );
+                task::spawn_blocking(move || {
+                    makepkg::build(&dir, &pkgdest, &log, build_user.as_ref(), prefix.as_deref())
+                })
+                .await
+                .unwrap_or_else(|err| Err(err.into()))
+            }
+        })
+        .buffered(jobs)
+        .collect()
+        .await
+}
+
+/// Explain the failed build of `pkgbase` and ask the user what to do about it, building it
+/// again until it works or gets skipped. None when skipped, an error when aborted.
+fn recover(
+    conf: &Config,
+    transaction: &mut Transaction,
+    pkgbase: &str,
+    dir: &Path,
+    pkgdest: &Path,
+    build_user: Option<&BuildUser>,
+    mut err: anyhow::Error,
+) -> Result<Option<Vec<PathBuf>>> {
+    let noconfirm = transaction.noconfirm();
+    let log = makepkg::log_path(conf, pkgbase);
+    loop {
+        error!("{err}");
+        let causes = diagnosis::diagnose(&fs::read_to_string(&log).unwrap_or_default());
+        diagnosis::print(&causes, pkgbase, &log);
+
+        let mut imported = false;
+        if let Some(keys) = diagnosis::unknown_keys(&causes) {
+            if !noconfirm && helpers::confirm(&tr!("import-keys-prompt"), true)? {
+                match import_keys(conf, pkgbase, keys, build_user) {
+                    Ok(()) => imported = true,
+                    Err(err) => error!("{err}"),
+                }
             }
         }
+        if !imported {
+            let recovery = if noconfirm {
+                Recovery::Abort
+            } else {
+                transaction::ask_recovery(pkgbase)?
+            };
+            match recovery {
+                Recovery::EditPkgbuild => transaction::edit_pkgbuild(dir, build_user)?,
+                Recovery::Retry => (),
+                Recovery::Shell => transaction::open_shell(dir, build_user)?,
+                Recovery::Skip => {
+                    transaction.skip(pkgbase);
+                    transaction.save(conf)?;
+                    return Ok(None);
+                }
+                Recovery::Abort => {
+                    transaction.save(conf)?;
+                    return Err(anyhow!(
+                        "{err}\n{}",
+                        tr!("transaction-saved", pkgbase = pkgbase)
+                    ));
+                }
+            }
+        }
+
+        match makepkg::build(dir, pkgdest, &log, build_user, None) {
+            Ok(built) => return Ok(Some(built)),
+            Err(retry_err) => err = retry_err,
+        }
+    }
+}
+
+/// Install what building `pkgbase` produced, and record it as built.
+fn install_built(
+    conf: &Config,
+    escalation: &Escalation,
+    local_repo: Option<&LocalRepo>,
+    transaction: &mut Transaction,
+    pkgbase: String,
+    built: Vec<PathBuf>,
+) -> Result<()> {
+    if let Some(local_repo) = local_repo {
+        local_repo.add(&built)?;
+    }
+
+    // only install the split packages that are part of the plan, targets explicitly
+    let (targets, deps): (Vec<_>, Vec<_>) = built
+        .iter()
+        .filter_map(|path| {
+            let name = makepkg::pkg_file_name(path)?;
+            let planned = transaction.plan.aur.iter().find(|pkg| pkg.name == name)?;
+            Some((path.clone(), planned))
+        })
+        .partition(|(_, planned)| planned.reason == "target");
+    for (pkgs, as_deps) in [(deps, true), (targets, false)] {
+        if pkgs.is_empty() {
+            continue;
+        }
+        let names = pkgs
+            .iter()
+            .map(|(_, planned)| planned.name.as_str())
+            .collect::<Vec<_>>();
+        let flags = flags_for(transaction, &names);
+        let paths = pkgs.into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+        trace!("installing {paths:?}, as dependencies : {as_deps}");
+        helpers::install_files(escalation, &paths, as_deps, &flags)?;
+    }
+
+    transaction.built.push(Built {
+        pkgbase,
+        paths: built,
+    });
+    transaction.save(conf)
+}
+
+/// Carry the transaction out from where it stopped, saving its state after every step.
+async fn execute(conf: &Config, transaction: &mut Transaction) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        warning,
+        clear,
+        ..
+    } = colors::theme();
+
+    let mut escalation = Escalation::new(conf);
+    let noconfirm = transaction.noconfirm();
+
+    // repo packages are pacman's business, dependencies of AUR packages included
+    if !transaction.repo_done {
+        let (repo_targets, repo_deps): (Vec<_>, Vec<_>) = transaction
+            .plan
+            .repo
+            .iter()
+            .partition(|pkg| pkg.reason == "target");
+        let qualified = |pkg: &PlanPkg| match &pkg.repo {
+            Some(repo) => format!("{repo}/{}", pkg.name),
+            None => pkg.name.clone(),
+        };
+        if !repo_targets.is_empty() {
+            escalation.authenticate()?;
+            let mut cmd = escalation.command("pacman");
+            cmd.arg("-S")
+                .args(flags_for(
+                    transaction,
+                    &repo_targets
+                        .iter()
+                        .map(|pkg| pkg.name.as_str())
+                        .collect::<Vec<_>>(),
+                ))
+                .args(repo_targets.iter().map(|pkg| qualified(pkg)));
+            helpers::run(&mut cmd)?;
+        }
+        if !repo_deps.is_empty() {
+            escalation.authenticate()?;
+            let flags = flags_for(
+                transaction,
+                &repo_deps
+                    .iter()
+                    .map(|pkg| pkg.name.as_str())
+                    .collect::<Vec<_>>(),
+            );
+            let mut cmd = escalation.command("pacman");
+            cmd.args(["-S", "--asdeps", "--needed"])
+                .args(helpers::without_reason_flags(&flags))
+                .args(repo_deps.iter().map(|pkg| qualified(pkg)));
+            helpers::run(&mut cmd)?;
+        }
+        transaction.repo_done = true;
+        transaction.save(conf)?;
+    }
+
+    let local_repo = conf.local_repo.then(|| LocalRepo::new(conf));
+    if !transaction.pending().is_empty() {
+        // ask for credentials now rather than in the middle of (or right after) a long build
+        escalation.authenticate()?;
+        let build_user = BuildUser::setup(conf)?;
+        let pkgdest = makepkg::pkgdest(conf);
+
+        loop {
+            let batch = transaction.ready();
+            if batch.is_empty() {
+                break;
+            }
+
+            // whatever may need the user is done before the builds start
+            let mut builds = Vec::new();
+            for pkgbase in batch {
+                let dir = makepkg::clone_or_pull(conf, &pkgbase, build_user.as_ref())?;
+                check_keys(conf, &dir, &pkgbase, build_user.as_ref(), noconfirm)?;
+                builds.push((pkgbase, dir));
+            }
+            let results = build_batch(conf, &builds, &pkgdest, build_user.as_ref()).await;
+
+            // failures come last, so that aborting doesn't leave the successful builds out
+            let (done, failed): (Vec<_>, Vec<_>) = builds
+                .into_iter()
+                .zip(results)
+                .partition(|(_, result)| result.is_ok());
+            for ((pkgbase, dir), result) in done.into_iter().chain(failed) {
+                let built = match result {
+                    Ok(built) => built,
+                    Err(err) => {
+                        let recovered = recover(
+                            conf,
+                            transaction,
+                            &pkgbase,
+                            &dir,
+                            &pkgdest,
+                            build_user.as_ref(),
+                            err,
+                        )?;
+                        match recovered {
+                            Some(built) => built,
+                            None => continue,
+                        }
+                    }
+                };
+                install_built(
+                    conf,
+                    &escalation,
+                    local_repo.as_ref(),
+                    transaction,
+                    pkgbase,
+                    built,
+                )?;
+            }
+        }
+    }
+
+    if !transaction.skipped.is_empty() {
+        println!(
+            "{warning}::{clear} {}",
+            tr!(
+                "skipped-packages",
+                count = transaction.skipped.len(),
+                packages = transaction.skipped.join(" ")
+            )
+        );
+    }
+
+    // make dependencies of skipped packages may not have been installed
+    let alpm = helpers::alpm(conf)?;
+    let remove_after = transaction
+        .plan
+        .remove_after
+        .iter()
+        .filter(|name| alpm.localdb().pkg(name.as_str()).is_ok())
+        .collect::<Vec<_>>();
+    if !remove_after.is_empty() {
+        let remove = match conf.delete_make_deps {
+            Some(remove) => remove,
+            // --noconfirm takes the prompt's default
+            None if noconfirm => false,
+            None => helpers::confirm(&tr!("remove-make-deps", count = remove_after.len()), false)?,
+        };
+        if remove {
+            let mut cmd = escalation.command("pacman");
+            cmd.arg("-Rns")
+                .args(
+                    transaction
+                        .pacman_flags
+                        .iter()
+                        .filter(|flag| *flag == "--noconfirm"),
+                )
+                .args(remove_after);
+            helpers::run(&mut cmd)?;
+        }
+    }
+
+    if let Some(local_repo) = &local_repo {
+        println!(
+            "{header}::{clear} {title}{}{clear}",
+            tr!("local-repo-ready", repo = local_repo.name().to_string())
+        );
+        info!(
+            "pacman.conf entry for it :\n{}",
+            local_repo.pacman_conf_entry()
+        );
+    }
+
+    Transaction::clear(conf)
+}
+
+/// The AUR packages called `packages`, failing if any of them doesn't exist.
+async fn aur_targets(aur: &Aur, packages: &[&str]) -> Result<Vec<raur::Package>> {
+    if packages.is_empty() {
+        return Ok(Vec::new());
+    }
+    let hits = aur.info(packages).await?;
+
+    if hits.len() != packages.len() {
+        let hit_names = hits.iter().map(|pkg| pkg.name.clone()).collect::<Vec<_>>();
+        let missing = packages
+            .iter()
+            .filter(|package| !hit_names.contains(&package.to_string()))
+            .copied()
+            .collect::<Vec<_>>();
+        let err_msg = tr!(
+            "packages-not-found",
+            count = missing.len(),
+            packages = missing.join(" ")
+        );
         error!("{}", err_msg);
         return Err(anyhow!("{}", err_msg));
     }
 
-    let formated_hits = hits.iter().map(|hit| hit.into()).collect::<Vec<Pkg>>();
-    let deps = DepTree::build_all(&formated_hits, &alpm, &raur).await?;
+    Ok(hits)
+}
 
-    let alpm = Alpm::new("/", "/var/lib/pacman/")?; // change this at some point to get it from
-                                                    // the pacman-conf command
-                                                    //let mut missing_deps = Vec::new();
-                                                    //let mut missing_make_deps = Vec::new();
-                                                    //let mut missing_check_deps = Vec::new();
+/// `-Si --graph` and `rah deptree` : the resolved dependency trees of AUR `packages`, as an
+/// indented tree or as Graphviz DOT.
+pub async fn deptree(packages: Vec<&str>, conf: &Config, refresh: bool, dot: bool) -> Result<()> {
+    let alpm = helpers::alpm(conf)?;
+    let aur = Aur::new(conf, refresh).await?;
+    let hits = aur_targets(&aur, &packages).await?;
 
-    for hit in hits {
-        //let deps =
-        //println!("to build : {:?}", deps.build);
-        //println!("not found : {:?}", deps.missing);
-        //println!("to install : {:?}", deps.install);
-        //println!("no need : {:?}", deps.unneeded);
-        todo!()
-        //trace!("checking dependencies for {} :", hit.name);
-        //let hit_missing_deps = helpers::check_deps(hit.depends)?;
-        //missing_deps.extend(hit_missing_deps);
-        //trace!("checking make dependencies for {} :", hit.name);
-        //let hit_missing_make_deps = helpers::check_deps(hit.make_depends)?;
-        //missing_make_deps.extend(hit_missing_make_deps);
-        //trace!("checking check dependencies for {} :", hit.name);
-        //let hit_missing_check_deps = helpers::check_deps(hit.check_depends)?;
-        //missing_check_deps.extend(hit_missing_check_deps);
-    }
+    let targets = hits.into_iter().map(Pkg::from).collect::<Vec<_>>();
+    let mut scheduler = AurScheduler::new(&aur, conf.aur_concurrency);
+    let trees = DepTree::resolve_all(&targets, &alpm, &mut scheduler).await?;
 
-    todo!();
+    if dot {
+        print!("{}", dep_graph::dot(&trees));
+    } else {
+        dep_graph::print_tree(&trees);
+    }
 
     Ok(())
 }
 
-pub async fn search(packages: Vec<&str>) -> Result<()> {
-    trace!("searching for packages {packages:?}");
+#[derive(Debug, PartialEq)]
+pub enum SearchFlag {
+    Installed,
+    OtherVersionInstalled(String),
+    OutOfDate { since: i64, last_modified: i64 },
+}
 
-    if packages.len() > 1 {
-        println!("{BOLD}{YELLOW_L}warning{CLEAR} : search will only account for the first argument passed.");
+/// Flags shown next to an AUR package in search results.
+pub fn search_flags(pkg: &raur::Package, localdb: &Db) -> Vec<SearchFlag> {
+    let mut flags = Vec::new();
+
+    // Fetch the pacman db (and/or our database ?) to see if searched packages are already
+    // installed with pacman
+    if let Ok(local_pkg) = localdb.pkg(pkg.name.as_str()) {
+        let local_pkg_ver = local_pkg.version().to_string();
+        if local_pkg_ver == pkg.version {
+            flags.push(SearchFlag::Installed);
+        } else {
+            flags.push(SearchFlag::OtherVersionInstalled(local_pkg_ver));
+        }
     }
 
-    let raur = raur::Handle::new();
+    if let Some(since) = pkg.out_of_date {
+        flags.push(SearchFlag::OutOfDate {
+            since,
+            last_modified: pkg.last_modified,
+        });
+    }
 
-    let hits = raur.search(packages[0]).await?;
+    flags
+}
 
-    println!(
-        "{BOLD}{BLUE}:: {WHITE}Found {} package{}{CLEAR}",
-        hits.len(),
-        if hits.len() != 1 { "s" } else { "" }
-    );
+/// Search flags as shown next to packages in text output.
+pub fn format_flags(flags: Vec<SearchFlag>) -> String {
+    let Theme {
+        installed,
+        other_version,
+        out_of_date,
+        clear,
+        ..
+    } = colors::theme();
 
-    let alpm = Alpm::new("/", "/var/lib/pacman/")?;
-    let localdb = alpm.localdb();
-    let mut pkg_flags: Vec<_> = vec![String::from("")];
+    flags
+        .into_iter()
+        .map(|flag| match flag {
+            SearchFlag::Installed => format!("{installed} [{}]{clear}", tr!("flag-installed")),
+            SearchFlag::OtherVersionInstalled(local_pkg_ver) => {
+                format!(
+                    "{other_version} [{}]{clear}",
+                    tr!("flag-other-version", version = local_pkg_ver)
+                )
+            }
+            SearchFlag::OutOfDate {
+                since,
+                last_modified,
+            } => {
+                let ood_str = Utc.timestamp_opt(since, 0).unwrap();
+                let last_mod_str = Utc.timestamp_opt(last_modified, 0).unwrap();
+                format!(
+                    "{out_of_date} [{}]{clear}",
+                    tr!(
+                        "flag-out-of-date",
+                        since = ood_str.format("%Y/%m/%d").to_string(),
+                        last_modified = last_mod_str.format("%Y/%m/%d").to_string()
+                    )
+                )
+            }
+        })
+        .collect()
+}
 
-    for pkg in hits {
-        pkg_flags.clear();
-        // Fetch the pacman db (and/or our database ?) to see if searched packages are already
-        // installed with pacman
-        let local_pkg = localdb.pkg(pkg.name.clone());
-
-        if let Ok(local_pkg) = local_pkg {
-            let local_pkg_ver = local_pkg.version().to_string();
-            if local_pkg_ver == pkg.version {
-                pkg_flags.push(format!("{CYAN} [installed]"))
-            } else {
-                pkg_flags.push(format!(
-                    "{CYAN_L} [other ver. installed ({})]",
-                    local_pkg_ver
-                ))
+/// An AUR search result, as emitted with `--json`.
+#[derive(Serialize)]
+pub struct SearchRecord {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub votes: u32,
+    pub popularity: f64,
+    pub installed: bool,
+    pub installed_version: Option<String>,
+    pub out_of_date: Option<i64>,
+    pub last_modified: i64,
+}
+
+impl SearchRecord {
+    pub fn new(pkg: &raur::Package, flags: &[SearchFlag]) -> Self {
+        let mut record = SearchRecord {
+            name: pkg.name.clone(),
+            version: pkg.version.clone(),
+            description: pkg.description.clone(),
+            votes: pkg.num_votes,
+            popularity: pkg.popularity,
+            installed: false,
+            installed_version: None,
+            out_of_date: None,
+            last_modified: pkg.last_modified,
+        };
+
+        for flag in flags {
+            match flag {
+                SearchFlag::Installed => {
+                    record.installed = true;
+                    record.installed_version = Some(pkg.version.clone());
+                }
+                SearchFlag::OtherVersionInstalled(local_pkg_ver) => {
+                    record.installed = true;
+                    record.installed_version = Some(local_pkg_ver.clone());
+                }
+                SearchFlag::OutOfDate { since, .. } => record.out_of_date = Some(*since),
             }
         }
 
-        // out of date
-        if let Some(pkg_ood) = pkg.out_of_date {
-            let ood_str = Utc.timestamp_opt(pkg_ood, 0).unwrap();
-            let last_mod_str = Utc.timestamp_opt(pkg.last_modified, 0).unwrap();
-            pkg_flags.push(format!(
-                "{RED} [out of date since {}, last update {}]",
-                ood_str.format("%Y/%m/%d"),
-                last_mod_str.format("%Y/%m/%d")
-            ))
+        record
+    }
+}
+
+/// Orders search results can be sorted in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortBy {
+    Votes,
+    Popularity,
+    Name,
+    Modified,
+    Submitted,
+}
+
+impl SortBy {
+    pub fn parse(sort_by: &str) -> Result<Self> {
+        match sort_by {
+            "votes" => Ok(SortBy::Votes),
+            "popularity" => Ok(SortBy::Popularity),
+            "name" => Ok(SortBy::Name),
+            "modified" => Ok(SortBy::Modified),
+            "submitted" => Ok(SortBy::Submitted),
+            other => Err(anyhow!("Unknown sort order \"{other}\"")),
+        }
+    }
+}
+
+/// Map a `--by` value to the RPC's search fields.
+pub fn parse_search_by(by: &str) -> Result<SearchBy> {
+    match by {
+        "name" => Ok(SearchBy::Name),
+        "name-desc" => Ok(SearchBy::NameDesc),
+        "maintainer" => Ok(SearchBy::Maintainer),
+        "depends" => Ok(SearchBy::Depends),
+        "makedepends" => Ok(SearchBy::MakeDepends),
+        "keywords" => Ok(SearchBy::Keywords),
+        other => Err(anyhow!("Unknown search field \"{other}\"")),
+    }
+}
+
+/// How search results get picked and ordered.
+pub struct SearchOptions {
+    pub by: SearchBy,
+    pub sort_by: Option<SortBy>,
+    pub reverse: bool,
+    pub limit: Option<usize>,
+    pub no_outdated: bool,
+    pub maintained_only: bool,
+    pub min_votes: u32,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            by: SearchBy::NameDesc,
+            sort_by: None,
+            reverse: false,
+            limit: None,
+            no_outdated: false,
+            maintained_only: false,
+            min_votes: 0,
         }
+    }
+}
+
+/// Packages matching every one of `terms`, filtered, sorted and limited according to `opts`.
+pub async fn search_hits(
+    aur: &Aur,
+    terms: &[&str],
+    opts: &SearchOptions,
+) -> Result<Vec<raur::Package>> {
+    let Some((first, rest)) = terms.split_first() else {
+        return Ok(Vec::new());
+    };
+
+    let mut hits = aur.search_by(first, opts.by.clone()).await?;
+    for term in rest {
+        let names = aur
+            .search_by(term, opts.by.clone())
+            .await?
+            .into_iter()
+            .map(|pkg| pkg.name)
+            .collect::<HashSet<_>>();
+        hits.retain(|pkg| names.contains(&pkg.name));
+    }
+
+    hits.retain(|pkg| {
+        !(opts.no_outdated && pkg.out_of_date.is_some())
+            && !(opts.maintained_only && pkg.maintainer.is_none())
+            && pkg.num_votes >= opts.min_votes
+    });
+
+    // most voted, popular or recent first, names in alphabetical order
+    if let Some(sort_by) = opts.sort_by {
+        hits.sort_by(|a, b| match sort_by {
+            SortBy::Votes => b.num_votes.cmp(&a.num_votes),
+            SortBy::Popularity => b.popularity.total_cmp(&a.popularity),
+            SortBy::Name => a.name.cmp(&b.name),
+            SortBy::Modified => b.last_modified.cmp(&a.last_modified),
+            SortBy::Submitted => b.first_submitted.cmp(&a.first_submitted),
+        });
+    }
+    if opts.reverse {
+        hits.reverse();
+    }
+    if let Some(limit) = opts.limit {
+        hits.truncate(limit);
+    }
+
+    Ok(hits)
+}
+
+pub async fn search(
+    packages: Vec<&str>,
+    conf: &Config,
+    refresh: bool,
+    opts: &SearchOptions,
+) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        name,
+        version,
+        dim,
+        clear,
+        ..
+    } = colors::theme();
+
+    trace!("searching for packages {packages:?}");
+
+    let aur = Aur::new(conf, refresh).await?;
+
+    let hits = search_hits(&aur, &packages, opts).await?;
+
+    let alpm = helpers::alpm(conf)?;
+    let localdb = alpm.localdb();
+
+    if output::is_custom(conf, "aur_search") {
+        let records = hits
+            .iter()
+            .map(|pkg| SearchRecord::new(pkg, &search_flags(pkg, localdb)))
+            .collect::<Vec<_>>();
+        return output::emit(conf, "aur_search", &records);
+    }
+
+    println!(
+        "{header}::{clear} {title}{}{clear}",
+        tr!("found-packages", count = hits.len())
+    );
+
+    for pkg in hits {
+        let pkg_flags = format_flags(search_flags(&pkg, localdb));
         // slow as f*ck, currently unusable, will probably have to think of another "batch" approach
         // TODO: Use pacman-conf to retrieve the database and do pacman's job ourself in order to
         // also be able to get info on whether a package is installed or provided by something else ?
@@ -142,170 +872,186 @@ pub async fn search(packages: Vec<&str>) -> Result<()> {
         */
 
         println!(
-            "{BOLD}{} {GREEN}{}{}\n{CLEAR}    {}",
+            "{name}{}{clear} {version}{}{clear}{}{clear}\n    {}",
             pkg.name,
             pkg.version,
-            pkg_flags.join(""),
+            pkg_flags,
             pkg.description
-                .unwrap_or(format!("{BLACK_L}No description.{CLEAR}"))
+                .unwrap_or(format!("{dim}{}{clear}", tr!("no-description")))
         );
     }
 
     Ok(())
 }
 
-pub async fn info(packages: Vec<&str>) -> Result<()> {
-    let raur = raur::Handle::new();
+/// Everything `-Si` knows about an AUR package, as emitted with `--json`.
+#[derive(Serialize)]
+pub struct InfoRecord {
+    pub name: String,
+    pub version: String,
+    pub package_base: String,
+    pub votes: u32,
+    pub popularity: f64,
+    pub description: Option<String>,
+    pub submitter: Option<String>,
+    pub maintainer: Option<String>,
+    pub co_maintainers: Vec<String>,
+    pub out_of_date: Option<i64>,
+    pub first_submitted: i64,
+    pub last_modified: i64,
+    pub git_clone_url: String,
+    pub url: Option<String>,
+    pub tarball_url: String,
+    pub licenses: Vec<String>,
+    pub groups: Vec<String>,
+    pub provides: Vec<String>,
+    pub depends: Vec<String>,
+    pub opt_depends: Vec<String>,
+    pub make_depends: Vec<String>,
+    pub check_depends: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub replaces: Vec<String>,
+    pub keywords: Vec<String>,
+}
 
-    let hits = raur.info(&packages).await?;
+impl InfoRecord {
+    pub fn new(pkg: &raur::Package, conf: &Config) -> Self {
+        InfoRecord {
+            name: pkg.name.clone(),
+            version: pkg.version.clone(),
+            package_base: pkg.package_base.clone(),
+            votes: pkg.num_votes,
+            popularity: pkg.popularity,
+            description: pkg.description.clone(),
+            submitter: pkg.submitter.clone(),
+            maintainer: pkg.maintainer.clone(),
+            co_maintainers: pkg.co_maintainers.clone(),
+            out_of_date: pkg.out_of_date,
+            first_submitted: pkg.first_submitted,
+            last_modified: pkg.last_modified,
+            git_clone_url: conf.aur_endpoint(&format!("{}.git", pkg.package_base)),
+            url: pkg.url.clone(),
+            tarball_url: conf.aur_endpoint(&pkg.url_path),
+            licenses: pkg.license.clone(),
+            groups: pkg.groups.clone(),
+            provides: pkg.provides.clone(),
+            depends: pkg.depends.clone(),
+            opt_depends: pkg.opt_depends.clone(),
+            make_depends: pkg.make_depends.clone(),
+            check_depends: pkg.check_depends.clone(),
+            conflicts: pkg.conflicts.clone(),
+            replaces: pkg.replaces.clone(),
+            keywords: pkg.keywords.clone(),
+        }
+    }
+}
+
+pub async fn info(packages: Vec<&str>, conf: &Config, refresh: bool) -> Result<()> {
+    let Theme {
+        header,
+        title,
+        dim,
+        out_of_date,
+        clear,
+        ..
+    } = colors::theme();
+
+    let aur = Aur::new(conf, refresh).await?;
+
+    let hits = aur.info(&packages).await?;
+
+    if output::is_custom(conf, "aur_info") {
+        let records = hits
+            .iter()
+            .map(|pkg| InfoRecord::new(pkg, conf))
+            .collect::<Vec<_>>();
+        return output::emit(conf, "aur_info", &records);
+    }
 
     println!(
-        "{BOLD}{BLUE}:: {WHITE}Found info for {} package{}{CLEAR}",
-        hits.len(),
-        if hits.len() != 1 { "s" } else { "" }
+        "{header}::{clear} {title}{}{clear}",
+        tr!("found-info", count = hits.len())
     );
 
-    for pkg in hits {
-        println!("{BOLD}Name \t\t\t: {CLEAR}{}", pkg.name);
-        println!("{BOLD}Version \t\t: {CLEAR}{}", pkg.version);
-        println!("{BOLD}Package base \t\t: {CLEAR}{}", pkg.package_base);
-        println!("{BOLD}Votes \t\t\t: {CLEAR}{}", pkg.num_votes);
-        println!("{BOLD}Popularity \t\t: {CLEAR}{}", pkg.popularity);
-        println!(
-            "{BOLD}Description \t\t: {CLEAR}{}",
-            pkg.description
-                .unwrap_or(format!("{BLACK_L}No description.{CLEAR}"))
-        );
-        println!(
-            "{BOLD}Submitter \t\t: {CLEAR}{}",
-            pkg.submitter
-                .unwrap_or(format!("{BLACK_L}No submitter.{CLEAR}"))
-        );
-        println!(
-            "{BOLD}Maintainer \t\t: {CLEAR}{}",
-            pkg.maintainer
-                .unwrap_or(format!("{BLACK_L}No maintainer.{CLEAR}"))
-        );
-        let co_maintainers = pkg.co_maintainers;
-        println!(
-            "{BOLD}Co-maintainers \t\t: {CLEAR}{}",
-            if co_maintainers.is_empty() {
-                format!("{BLACK_L}No co-maintainers.{CLEAR}")
-            } else {
-                co_maintainers.join(", ")
-            }
-        );
-        if let Some(pkg_ood) = pkg.out_of_date {
-            let ood_ts = Utc.timestamp_opt(pkg_ood, 0).unwrap();
-            println!(
-                "{BOLD}Out of date \t\t: {RED}Flagged out of date since {}{CLEAR}",
-                ood_ts.format("%Y-%m-%d %H: %M (UTC)")
-            )
+    let or_none = |value: Option<String>, none: &str| {
+        value.unwrap_or_else(|| format!("{dim}{}{clear}", tr!(none)))
+    };
+    let list = |values: Vec<String>, none: &str| {
+        if values.is_empty() {
+            format!("{dim}{}{clear}", tr!(none))
         } else {
-            println!("{BOLD}Out of date \t\t: {CLEAR}{BLACK_L}Not flagged out of date{CLEAR}");
+            values.join(", ")
         }
-        let first_sub_ts = Utc.timestamp_opt(pkg.first_submitted, 0).unwrap();
-        println!(
-            "{BOLD}First submitted \t: {CLEAR}{}",
-            first_sub_ts.format("%Y-%m-%d %H: %M (UTC)")
-        );
-        let last_mod_ts = Utc.timestamp_opt(pkg.last_modified, 0).unwrap();
-        println!(
-            "{BOLD}Last updated \t\t: {CLEAR}{}",
-            last_mod_ts.format("%Y-%m-%d %H: %M (UTC)")
-        );
+    };
+    let date = |ts: i64| {
+        Utc.timestamp_opt(ts, 0)
+            .unwrap()
+            .format("%Y-%m-%d %H: %M (UTC)")
+            .to_string()
+    };
 
-        println!(
-            "{BOLD}Git clone URL \t\t: {CLEAR}{AUR_URL}{}.git",
-            pkg.package_base
-        );
-        println!(
-            "{BOLD}Upstream URL \t\t: {CLEAR}{}",
-            pkg.url.unwrap_or(format!("{BLACK_L}No upstream URL."))
-        );
-        println!("{BOLD}Tarball URL \t\t: {CLEAR}{AUR_URL}{}", pkg.url_path);
-        println!("{BOLD}Licenses \t\t: {CLEAR}{}", pkg.license.join(", "));
-        let groups = pkg.groups;
-        println!(
-            "{BOLD}Groups \t\t\t: {CLEAR}{}",
-            if groups.is_empty() {
-                format!("{BLACK_L}No groups.{CLEAR}")
-            } else {
-                groups.join(", ")
-            }
-        );
-        let provides = pkg.provides;
-        println!(
-            "{BOLD}Provides \t\t: {CLEAR}{}",
-            if provides.is_empty() {
-                format!("{BLACK_L}No provides.{CLEAR}")
-            } else {
-                provides.join(", ")
-            }
-        );
-        let depends = pkg.depends;
-        println!(
-            "{BOLD}Depends \t\t: {CLEAR}{}",
-            if depends.is_empty() {
-                format!("{BLACK_L}No dependencies.{CLEAR}")
-            } else {
-                depends.join(", ")
-            }
-        );
-        let opt_depends = pkg.opt_depends;
-        println!(
-            "{BOLD}Opt. dependencies \t: {CLEAR}{}",
-            if opt_depends.is_empty() {
-                format!("{BLACK_L}No optionnal dependencies.{CLEAR}")
-            } else {
-                opt_depends.join(", ")
-            }
-        );
-        let make_depends = pkg.make_depends;
-        println!(
-            "{BOLD}Make dependencies \t: {CLEAR}{}",
-            if make_depends.is_empty() {
-                format!("{BLACK_L}No make dependencies.{CLEAR}")
-            } else {
-                make_depends.join(", ")
-            }
-        );
-        let check_depends = pkg.check_depends;
-        println!(
-            "{BOLD}Check dependencies \t: {CLEAR}{}",
-            if check_depends.is_empty() {
-                format!("{BLACK_L}No check dependencies.{CLEAR}")
-            } else {
-                check_depends.join(", ")
-            }
-        );
-        let conflicts = pkg.conflicts;
-        println!(
-            "{BOLD}Conflicts \t\t: {CLEAR}{}",
-            if conflicts.is_empty() {
-                format!("{BLACK_L}No conflicts.{CLEAR}")
-            } else {
-                conflicts.join(", ")
-            }
-        );
-        let replaces = pkg.replaces;
-        println!(
-            "{BOLD}Replaces \t\t: {CLEAR}{}",
-            if replaces.is_empty() {
-                format!("{BLACK_L}No replaces.{CLEAR}")
-            } else {
-                replaces.join(", ")
-            }
-        );
-        let keywords = pkg.keywords;
-        println!(
-            "{BOLD}Keywords\t\t: {CLEAR}{}",
-            if keywords.is_empty() {
-                format!("{BLACK_L}No keywords.{CLEAR}")
-            } else {
-                keywords.join(", ")
-            }
-        );
+    for pkg in hits {
+        let flagged = match pkg.out_of_date {
+            Some(since) => format!(
+                "{out_of_date}{}{clear}",
+                tr!("flagged-out-of-date", since = date(since))
+            ),
+            None => format!("{dim}{}{clear}", tr!("not-out-of-date")),
+        };
+        helpers::print_fields(&[
+            (tr!("info-name"), pkg.name),
+            (tr!("info-version"), pkg.version),
+            (tr!("info-package-base"), pkg.package_base.clone()),
+            (tr!("info-votes"), pkg.num_votes.to_string()),
+            (tr!("info-popularity"), pkg.popularity.to_string()),
+            (
+                tr!("info-description"),
+                or_none(pkg.description, "no-description"),
+            ),
+            (
+                tr!("info-submitter"),
+                or_none(pkg.submitter, "no-submitter"),
+            ),
+            (
+                tr!("info-maintainer"),
+                or_none(pkg.maintainer, "no-maintainer"),
+            ),
+            (
+                tr!("info-co-maintainers"),
+                list(pkg.co_maintainers, "no-co-maintainers"),
+            ),
+            (tr!("info-out-of-date"), flagged),
+            (tr!("info-first-submitted"), date(pkg.first_submitted)),
+            (tr!("info-last-updated"), date(pkg.last_modified)),
+            (
+                tr!("info-git-clone-url"),
+                conf.aur_endpoint(&format!("{}.git", pkg.package_base)),
+            ),
+            (
+                tr!("info-upstream-url"),
+                or_none(pkg.url, "no-upstream-url"),
+            ),
+            (tr!("info-tarball-url"), conf.aur_endpoint(&pkg.url_path)),
+            (tr!("info-licenses"), pkg.license.join(", ")),
+            (tr!("info-groups"), list(pkg.groups, "no-groups")),
+            (tr!("info-provides"), list(pkg.provides, "no-provides")),
+            (tr!("info-depends"), list(pkg.depends, "no-depends")),
+            (
+                tr!("info-opt-depends"),
+                list(pkg.opt_depends, "no-opt-depends"),
+            ),
+            (
+                tr!("info-make-depends"),
+                list(pkg.make_depends, "no-make-depends"),
+            ),
+            (
+                tr!("info-check-depends"),
+                list(pkg.check_depends, "no-check-depends"),
+            ),
+            (tr!("info-conflicts"), list(pkg.conflicts, "no-conflicts")),
+            (tr!("info-replaces"), list(pkg.replaces, "no-replaces")),
+            (tr!("info-keywords"), list(pkg.keywords, "no-keywords")),
+        ]);
         println!("")
     }
     Ok(())
@@ -0,0 +1,213 @@
+use {
+    crate::colors::{self, CLEAR},
+    anyhow::{anyhow, Result},
+    chrono::{TimeZone, Utc},
+    serde_json::Value,
+    std::fmt::Write,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Literal(String),
+    Field {
+        name: String,
+        date_format: Option<String>,
+    },
+    Color(String),
+    Cond {
+        field: String,
+        negated: bool,
+        body: Vec<Node>,
+    },
+}
+
+/// A level of nesting while parsing : the field and whether it is negated for a conditional,
+/// none for the top level, with the nodes found in it so far.
+type Level = (Option<(String, bool)>, Vec<Node>);
+
+/// A user-defined output format for records, e.g.
+/// `{$bold}{name} {$green}{version}{$clear}{?out_of_date} (out of date since {out_of_date:%Y-%m-%d}){/out_of_date}`
+///
+/// - `{field}` prints a field, lists being joined with ", "
+/// - `{field:FORMAT}` prints a timestamp field with a chrono/strftime format
+/// - `{?field}...{/field}` only prints its content if the field is set (not null, empty, false or 0),
+///   `{!field}...{/field}` only if it isn't
+/// - `{$color}` switches to a color role of the theme or to one of the `STYLES`
+/// - `{{`, `}}`, `\n`, `\t` and `\\` escape braces, newlines, tabs and backslashes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(src: &str) -> Result<Self> {
+        // nodes of the currently open conditionals, the outermost level first
+        let mut stack: Vec<Level> = vec![(None, Vec::new())];
+        let mut literal = String::new();
+        let mut chars = src.chars().peekable();
+
+        fn flush(literal: &mut String, nodes: &mut Vec<Node>) {
+            if !literal.is_empty() {
+                nodes.push(Node::Literal(std::mem::take(literal)));
+            }
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => literal.push('\n'),
+                    Some('t') => literal.push('\t'),
+                    Some(other) => literal.push(other),
+                    None => literal.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(anyhow!("Unmatched \"}}\" in template \"{src}\"")),
+                '{' => {
+                    let mut tag = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => tag.push(c),
+                            None => return Err(anyhow!("Unclosed \"{{\" in template \"{src}\"")),
+                        }
+                    }
+                    flush(&mut literal, &mut stack.last_mut().unwrap().1);
+
+                    if let Some(field) = tag.strip_prefix('?') {
+                        stack.push((Some((field.to_string(), false)), Vec::new()));
+                    } else if let Some(field) = tag.strip_prefix('!') {
+                        stack.push((Some((field.to_string(), true)), Vec::new()));
+                    } else if let Some(field) = tag.strip_prefix('/') {
+                        let (cond, body) = stack.pop().unwrap();
+                        match cond {
+                            Some((open, negated)) if open == field => {
+                                stack.last_mut().unwrap().1.push(Node::Cond {
+                                    field: open,
+                                    negated,
+                                    body,
+                                })
+                            }
+                            _ => {
+                                return Err(anyhow!(
+                                    "\"{{/{field}}}\" doesn't close any conditional in template \"{src}\""
+                                ))
+                            }
+                        }
+                    } else if let Some(color) = tag.strip_prefix('$') {
+                        if !colors::is_style(color) {
+                            return Err(anyhow!("Unknown color \"{color}\" in template \"{src}\""));
+                        }
+                        stack
+                            .last_mut()
+                            .unwrap()
+                            .1
+                            .push(Node::Color(color.to_string()));
+                    } else {
+                        let (name, date_format) = match tag.split_once(':') {
+                            Some((name, format)) => (name, Some(format.to_string())),
+                            None => (tag.as_str(), None),
+                        };
+                        stack.last_mut().unwrap().1.push(Node::Field {
+                            name: name.to_string(),
+                            date_format,
+                        });
+                    }
+                }
+                c => literal.push(c),
+            }
+        }
+
+        flush(&mut literal, &mut stack.last_mut().unwrap().1);
+        match stack.pop() {
+            Some((None, nodes)) if stack.is_empty() => Ok(Template { nodes }),
+            Some((Some((field, _)), _)) => Err(anyhow!(
+                "Conditional on \"{field}\" is never closed in template \"{src}\""
+            )),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Render `record`, a JSON object, dropping the color tags unless `color` is set.
+    pub fn render(&self, record: &Value, color: bool) -> Result<String> {
+        let mut out = String::new();
+        render_nodes(&self.nodes, record, color, &mut out)?;
+        // don't let colors bleed into whatever comes next
+        if color && out.contains('\x1b') {
+            out.push_str(CLEAR);
+        }
+        Ok(out)
+    }
+}
+
+fn lookup<'a>(record: &'a Value, field: &str) -> Result<&'a Value> {
+    record
+        .get(field)
+        .ok_or(anyhow!("Unknown field \"{field}\" in template"))
+}
+
+fn is_set(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(to_text).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+fn render_nodes(nodes: &[Node], record: &Value, color: bool, out: &mut String) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Literal(text) => out.push_str(text),
+            Node::Color(name) => {
+                if color {
+                    out.push_str(&colors::theme().style(name).unwrap_or_default())
+                }
+            }
+            Node::Field { name, date_format } => {
+                let value = lookup(record, name)?;
+                match (date_format, value) {
+                    (_, Value::Null) => (),
+                    (None, value) => out.push_str(&to_text(value)),
+                    (Some(format), value) => {
+                        let date = value
+                            .as_i64()
+                            .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+                            .ok_or(anyhow!("Field \"{name}\" isn't a timestamp"))?;
+                        // invalid format specifiers only show up when formatting
+                        write!(out, "{}", date.format(format))
+                            .map_err(|_| anyhow!("Invalid date format \"{format}\""))?;
+                    }
+                }
+            }
+            Node::Cond {
+                field,
+                negated,
+                body,
+            } => {
+                if is_set(lookup(record, field)?) != *negated {
+                    render_nodes(body, record, color, out)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use {
+    crate::{
+        colors::{self, Theme},
+        config::Config,
+        plan::{Plan, PlanPkg},
+        privilege::{self, BuildUser},
+        tr,
+    },
+    anyhow::{Context, Result},
+    log::debug,
+    serde::{Deserialize, Serialize},
+    std::{
+        env, fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+const STATE_FILE: &str = "transaction.json";
+
+/// A package base built and installed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Built {
+    pub pkgbase: String,
+    pub paths: Vec<PathBuf>,
+}
+
+/// An `-S` in progress, saved in the database directory after every step so that
+/// `rah --resume` can pick it up where it failed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Transaction {
+    pub plan: Plan,
+    /// pacman flags the transaction was started with.
+    pub pacman_flags: Vec<String>,
+    /// Whether the repo packages are installed.
+    pub repo_done: bool,
+    pub built: Vec<Built>,
+    /// Package bases left out after failing to build, along with their dependents.
+    pub skipped: Vec<String>,
+}
+
+/// What to do about a failed build.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Recovery {
+    EditPkgbuild,
+    Retry,
+    /// Leave the package base and the packages depending on it out.
+    Skip,
+    Shell,
+    Abort,
+}
+
+/// What to do about the transaction left unfinished when starting a new one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unfinished {
+    Resume,
+    /// Forget it, and carry on with the new one.
+    Discard,
+    Abort,
+}
+
+fn state_file(conf: &Config) -> PathBuf {
+    Path::new(&conf.db_path).join(STATE_FILE)
+}
+
+fn pkg_base(pkg: &PlanPkg) -> String {
+    pkg.base.clone().unwrap_or_else(|| pkg.name.clone())
+}
+
+impl Transaction {
+    pub fn new(plan: Plan, pacman_flags: Vec<String>) -> Self {
+        Transaction {
+            plan,
+            pacman_flags,
+            repo_done: false,
+            built: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    pub fn noconfirm(&self) -> bool {
+        self.pacman_flags.iter().any(|flag| flag == "--noconfirm")
+    }
+
+    /// The transaction left unfinished, if any.
+    pub fn load(conf: &Config) -> Result<Option<Self>> {
+        let path = state_file(conf);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(&path)?;
+        let transaction = serde_json::from_slice(&data)
+            .with_context(|| format!("Invalid transaction state at {path:?}"))?;
+
+        Ok(Some(transaction))
+    }
+
+    pub fn save(&self, conf: &Config) -> Result<()> {
+        let path = state_file(conf);
+        debug!("saving the transaction state to {path:?}");
+        fs::create_dir_all(&conf.db_path)?;
+        let tmp = path.with_extension("json.part");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Forget the transaction, once it is done.
+    pub fn clear(conf: &Config) -> Result<()> {
+        match fs::remove_file(state_file(conf)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Package bases still to build, dependencies first.
+    pub fn pending(&self) -> Vec<String> {
+        let mut pkgbases: Vec<String> = Vec::new();
+        for pkg in &self.plan.aur {
+            let pkgbase = pkg_base(pkg);
+            let done = self.built.iter().any(|built| built.pkgbase == pkgbase)
+                || self.skipped.contains(&pkgbase);
+            if !done && !pkgbases.contains(&pkgbase) {
+                pkgbases.push(pkgbase);
+            }
+        }
+        pkgbases
+    }
+
+    /// Package base of the AUR package `name` of the plan.
+    fn base_of(&self, name: &str) -> Option<String> {
+        self.plan
+            .aur
+            .iter()
+            .find(|pkg| pkg.name == name)
+            .map(pkg_base)
+    }
+
+    /// Pending package bases that can be built now, each of their AUR dependencies being
+    /// built. They don't depend on each other, so they can be built at once.
+    pub fn ready(&self) -> Vec<String> {
+        let pending = self.pending();
+        let ready = pending
+            .iter()
+            .filter(|base| {
+                self.plan
+                    .aur
+                    .iter()
+                    .filter(|pkg| pkg_base(pkg) == **base)
+                    .flat_map(|pkg| &pkg.aur_depends)
+                    .filter_map(|dep| self.base_of(dep))
+                    .all(|dep_base| dep_base == **base || !pending.contains(&dep_base))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // should the dependencies go round in circles, one at a time in the plan's order
+        if ready.is_empty() {
+            return pending.into_iter().take(1).collect();
+        }
+        ready
+    }
+
+    /// Leave `pkgbase` out, along with every AUR package of the plan depending on it, directly
+    /// or not.
+    pub fn skip(&mut self, pkgbase: &str) {
+        let mut skipped = vec![pkgbase.to_string()];
+        loop {
+            let more = self
+                .plan
+                .aur
+                .iter()
+                .filter(|pkg| {
+                    pkg.aur_depends.iter().any(|dep| {
+                        self.base_of(dep)
+                            .is_some_and(|dep_base| skipped.contains(&dep_base))
+                    })
+                })
+                .map(pkg_base)
+                .filter(|base| !skipped.contains(base))
+                .collect::<Vec<_>>();
+            if more.is_empty() {
+                break;
+            }
+            skipped.extend(more);
+        }
+
+        for pkgbase in skipped {
+            if !self.skipped.contains(&pkgbase) {
+                self.skipped.push(pkgbase);
+            }
+        }
+    }
+}
+
+/// Ask on the terminal what to do about the failed build of `pkgbase`, aborting by default.
+pub fn ask_recovery(pkgbase: &str) -> Result<Recovery> {
+    ask_choice(
+        &tr!("build-failed", pkgbase = pkgbase),
+        &[
+            ("recovery-edit", Recovery::EditPkgbuild),
+            ("recovery-retry", Recovery::Retry),
+            ("recovery-skip", Recovery::Skip),
+            ("recovery-shell", Recovery::Shell),
+            ("recovery-abort", Recovery::Abort),
+        ],
+        Recovery::Abort,
+    )
+}
+
+/// Ask what to do about the unfinished transaction, aborting when there is no answer.
+pub fn ask_unfinished() -> Result<Unfinished> {
+    ask_choice(
+        &tr!("unfinished-transaction"),
+        &[
+            ("unfinished-resume", Unfinished::Resume),
+            ("unfinished-discard", Unfinished::Discard),
+            ("unfinished-abort", Unfinished::Abort),
+        ],
+        Unfinished::Abort,
+    )
+}
+
+/// Ask `question`, offering the numbered `choices` given by their message id. `default` is
+/// the answer when there is none.
+fn ask_choice<T: Copy>(question: &str, choices: &[(&str, T)], default: T) -> Result<T> {
+    let Theme {
+        prompt,
+        title,
+        clear,
+        ..
+    } = colors::theme();
+
+    println!("{prompt}::{clear} {title}{question}{clear}");
+    for (i, (id, _)) in choices.iter().enumerate() {
+        println!("   {} {}", i + 1, tr!(id));
+    }
+
+    loop {
+        print!("{prompt}==>{clear} [1-{}] ", choices.len());
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(default);
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(default);
+        }
+        if let Some((_, choice)) = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| choices.get(i.wrapping_sub(1)))
+        {
+            return Ok(*choice);
+        }
+    }
+}
+
+/// Run `$VISUAL`, `$EDITOR` or vi on the PKGBUILD of `dir`.
+pub fn edit_pkgbuild(dir: &Path, build_user: Option<&BuildUser>) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let mut cmd = privilege::build_command(build_user, program);
+    cmd.args(words).arg("PKGBUILD").current_dir(dir);
+    // whatever the editor returns, the build is retried and fails again if it has to
+    let _ = cmd.status()?;
+
+    Ok(())
+}
+
+/// Open `$SHELL` in `dir`, for the user to look around or fix the build by hand.
+pub fn open_shell(dir: &Path, build_user: Option<&BuildUser>) -> Result<()> {
+    let shell = env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"));
+    let _ = privilege::build_command(build_user, &shell)
+        .current_dir(dir)
+        .status()?;
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use {
+    common::{aur_packages, MockAur},
+    rah::{
+        aur::Aur,
+        aur_scheduler::AurScheduler,
+        sync::{search_hits, InfoRecord, SearchOptions, SortBy},
+    },
+    raur::SearchBy,
+};
+
+#[tokio::test]
+async fn info_returns_requested_packages() {
+    let mock = MockAur::start(aur_packages());
+    let aur = Aur::new(&mock.config("info"), false).await.unwrap();
+
+    let hits = aur.info(&["libfoo", "rah-test-app", "nope"]).await.unwrap();
+    let mut names = hits.iter().map(|pkg| pkg.name.as_str()).collect::<Vec<_>>();
+    names.sort();
+
+    assert_eq!(names, ["libfoo", "rah-test-app"]);
+    let app = hits.iter().find(|pkg| pkg.name == "rah-test-app").unwrap();
+    assert_eq!(app.depends, ["libfoo>=2.0", "glibc"]);
+    assert_eq!(app.make_depends, ["rah-test-build-tool"]);
+}
+
+#[tokio::test]
+async fn search_matches_name_and_description() {
+    let mock = MockAur::start(aur_packages());
+    let aur = Aur::new(&mock.config("search"), false).await.unwrap();
+
+    let hits = aur.search("foo library").await.unwrap();
+    let mut names = hits.iter().map(|pkg| pkg.name.as_str()).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["libfoo", "libfoo-git"]);
+
+    let hits = aur.search_by("bob", SearchBy::Maintainer).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].name, "libfoo-git");
+}
+
+#[tokio::test]
+async fn scheduler_prefers_exact_name_hits() {
+    let mock = MockAur::start(aur_packages());
+    let aur = Aur::new(&mock.config("exact"), false).await.unwrap();
+    let mut scheduler = AurScheduler::new(&aur, 4);
+
+    let deps = vec![
+        String::from("libfoo>=2.0"),
+        String::from("rah-test-build-tool"),
+    ];
+    scheduler.resolve(&deps).await.unwrap();
+
+    assert_eq!(scheduler.get("libfoo>=2.0").unwrap().name, "libfoo");
+    assert_eq!(
+        scheduler.get("rah-test-build-tool").unwrap().name,
+        "rah-test-build-tool"
+    );
+}
+
+#[tokio::test]
+async fn scheduler_picks_provider_satisfying_version() {
+    let mock = MockAur::start(aur_packages());
+    let aur = Aur::new(&mock.config("provides"), false).await.unwrap();
+    let mut scheduler = AurScheduler::new(&aur, 4);
+
+    let deps = vec![
+        String::from("java-runtime>=11"),
+        String::from("java-runtime"),
+        String::from("libfoo>=3"),
+        String::from("rah-test-nonexistent"),
+    ];
+    scheduler.resolve(&deps).await.unwrap();
+
+    // the most voted provider is too old
+    assert_eq!(
+        scheduler.get("java-runtime>=11").unwrap().name,
+        "jre-test-bin"
+    );
+    assert_eq!(scheduler.get("java-runtime").unwrap().name, "jre8-test-bin");
+    assert!(scheduler.get("libfoo>=3").is_none());
+    assert!(scheduler.get("rah-test-nonexistent").is_none());
+}
+
+#[tokio::test]
+async fn scheduler_memoizes_lookups() {
+    let mock = MockAur::start(aur_packages());
+    let aur = Aur::new(&mock.config("memo"), false).await.unwrap();
+    let mut scheduler = AurScheduler::new(&aur, 4);
+
+    let deps = vec![String::from("java-runtime>=11"), String::from("libfoo")];
+    scheduler.resolve(&deps).await.unwrap();
+    let requests = mock.requests();
+
+    scheduler.resolve(&deps).await.unwrap();
+    scheduler.info(&["libfoo", "jre-test-bin"]).await.unwrap();
+
+    assert_eq!(mock.requests(), requests);
+}
+
+#[tokio::test]
+async fn metadata_cache_answers_offline() {
+    let mock = MockAur::start(aur_packages());
+    let mut conf = mock.config("cache");
+    conf.aur_cache = true;
+
+    let aur = Aur::new(&conf, true).await.unwrap();
+    assert_eq!(aur.info(&["libfoo"]).await.unwrap().len(), 1);
+
+    // the dump is only downloaded once, queries are answered locally
+    let requests = mock.requests();
+    conf.offline = true;
+    let aur = Aur::new(&conf, false).await.unwrap();
+
+    let hits = aur
+        .search_by("java-runtime", SearchBy::Provides)
+        .await
+        .unwrap();
+    let mut names = hits.iter().map(|pkg| pkg.name.as_str()).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["jre-test-bin", "jre8-test-bin"]);
+    assert!(aur.info(&["not-in-cache"]).await.unwrap().is_empty());
+    assert_eq!(mock.requests(), requests);
+}
+
+#[tokio::test]
+async fn info_records_keep_every_field() {
+    let mock = MockAur::start(aur_packages());
+    let conf = mock.config("records");
+    let aur = Aur::new(&conf, false).await.unwrap();
+
+    let hits = aur.info(&["libfoo-git"]).await.unwrap();
+    let record = serde_json::to_value(InfoRecord::new(&hits[0], &conf)).unwrap();
+
+    assert_eq!(record["name"], "libfoo-git");
+    assert_eq!(record["maintainer"], "bob");
+    assert_eq!(record["provides"], serde_json::json!(["libfoo=2.2"]));
+    assert_eq!(
+        record["git_clone_url"],
+        format!("{}libfoo-git.git", mock.url())
+    );
+    assert!(record["out_of_date"].is_null());
+}
+
+#[tokio::test]
+async fn search_intersects_terms_then_filters_and_sorts() {
+    let mock = MockAur::start(aur_packages());
+    let aur = Aur::new(&mock.config("search-opts"), false).await.unwrap();
+    let names = |hits: Vec<raur::Package>| hits.into_iter().map(|pkg| pkg.name).collect::<Vec<_>>();
+
+    let opts = SearchOptions::default();
+    let hits = search_hits(&aur, &["foo", "git"], &opts).await.unwrap();
+    assert_eq!(names(hits), ["libfoo-git"]);
+
+    let opts = SearchOptions {
+        by: SearchBy::Name,
+        sort_by: Some(SortBy::Votes),
+        min_votes: 5,
+        ..Default::default()
+    };
+    let hits = search_hits(&aur, &["rah-test"], &opts).await.unwrap();
+    assert_eq!(
+        names(hits),
+        ["rah-test-app", "rah-test-split-a", "rah-test-split-b"]
+    );
+
+    let opts = SearchOptions {
+        by: SearchBy::Name,
+        sort_by: Some(SortBy::Name),
+        reverse: true,
+        limit: Some(2),
+        maintained_only: true,
+        ..Default::default()
+    };
+    let hits = search_hits(&aur, &["rah-test"], &opts).await.unwrap();
+    assert_eq!(names(hits), ["rah-test-split-b", "rah-test-split-a"]);
+}
@@ -0,0 +1,111 @@
+use rah::cli::{self, Flag, PacmanArgs};
+
+fn parse(args: &str) -> PacmanArgs {
+    let args = args
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    PacmanArgs::parse(&cli::command(), &args)
+}
+
+#[test]
+fn combined_flags_are_split() {
+    let args = parse("-Syu --needed foo bar");
+    assert_eq!(args.op, Some('S'));
+    assert_eq!(args.known, [Flag::Short('y'), Flag::Long("needed".into())]);
+    assert_eq!(args.unknown, [Flag::Short('u')]);
+    assert_eq!(args.targets, ["foo", "bar"]);
+
+    let args = parse("--remove -ns --dbpath /tmp/db foo");
+    assert_eq!(args.op, Some('R'));
+    assert_eq!(args.unknown.len(), 3);
+    assert_eq!(args.targets, ["foo"]);
+}
+
+#[test]
+fn rah_handles_its_own_operations() {
+    for args in [
+        "-Ss foo",
+        "-Syi foo",
+        "-S --asdeps foo",
+        "-Qs foo",
+        "foo bar",
+        "-Ss foo --json",
+        "-Si --graph foo",
+        "deptree foo",
+        "-Qi --required-by foo",
+    ] {
+        assert!(
+            parse(args).handled_by_rah(),
+            "{args} should be handled by rah"
+        );
+    }
+    for args in [
+        "-Syu",
+        "-Sy",
+        "-Sc",
+        "-Qqm",
+        "-Q foo",
+        "-Rns foo",
+        "-U ./foo.pkg.tar.zst",
+        "-Fy",
+    ] {
+        assert!(!parse(args).handled_by_rah(), "{args} should go to pacman");
+    }
+}
+
+#[test]
+fn forwarded_commands_lose_rah_options() {
+    let args = parse("-c rah.toml --offline -Rns --color never foo --json");
+    assert_eq!(args.forwarded, ["-Rns", "--color", "never", "foo"]);
+    assert_eq!(args.own, ["-c", "rah.toml", "--offline", "--json"]);
+    assert!(args.needs_root());
+
+    let args = parse("-Syu --jobs 4");
+    assert_eq!(args.forwarded, ["-Syu"]);
+    assert_eq!(args.own, ["--jobs", "4"]);
+
+    // -c only is rah's config before the operation
+    let args = parse("-Scc");
+    assert_eq!(args.forwarded, ["-Scc"]);
+    assert!(args.own.is_empty());
+}
+
+#[test]
+fn only_system_changes_need_root() {
+    for args in [
+        "-Syu",
+        "-Sc",
+        "-Rns foo",
+        "-U foo",
+        "-Fy",
+        "-D --asdeps foo",
+    ] {
+        assert!(parse(args).needs_root(), "{args} should need root");
+    }
+    for args in [
+        "-Ssq foo", "-Sl", "-Qqm", "-Fl foo", "-T foo", "-Rp foo", "-Dk",
+    ] {
+        assert!(!parse(args).needs_root(), "{args} shouldn't need root");
+    }
+}
+
+#[test]
+fn clap_accepts_what_rah_handles() {
+    for args in [
+        "rah -Syi foo",
+        "rah -S --needed --asdeps foo",
+        "rah -Ss foo --sortby votes",
+        "rah -Si --graph foo",
+        "rah -Si --graph --dot foo bar",
+        "rah deptree --dot foo",
+        "rah -S --print-plan foo",
+        "rah -Qi --required-by foo",
+        "rah whyneeded foo bar",
+        "rah -S --jobs 4 foo bar",
+        "rah --jobs 4 --resume",
+    ] {
+        let args = args.split_whitespace().collect::<Vec<_>>();
+        assert!(cli::command().try_get_matches_from(args).is_ok());
+    }
+}
@@ -0,0 +1,30 @@
+use {
+    rah::colors::{self, Theme},
+    std::collections::HashMap,
+};
+
+#[test]
+fn disabled_themes_print_nothing() {
+    let theme = Theme::new(false, &HashMap::new());
+
+    assert!(theme.clear.is_empty() && theme.name.is_empty() && theme.error.is_empty());
+    assert_eq!(theme.style("red").as_deref(), Some(""));
+    assert_eq!(theme.style("version").as_deref(), Some(""));
+    assert_eq!(theme.style("pink"), None);
+}
+
+#[test]
+fn roles_can_be_restyled() {
+    let roles = HashMap::from([(String::from("version"), String::from("bold cyan"))]);
+    colors::check_roles(&roles).unwrap();
+    let theme = Theme::new(true, &roles);
+
+    assert_eq!(theme.version, "\x1b[1m\x1b[36m");
+    assert_eq!(theme.error, "\x1b[1m\x1b[31m");
+    assert_eq!(theme.style("version"), Some(theme.version.clone()));
+
+    let unknown_role = HashMap::from([(String::from("nope"), String::from("red"))]);
+    assert!(colors::check_roles(&unknown_role).is_err());
+    let unknown_style = HashMap::from([(String::from("name"), String::from("pink"))]);
+    assert!(colors::check_roles(&unknown_style).is_err());
+}
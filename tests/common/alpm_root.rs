@@ -0,0 +1,160 @@
+use {
+    super::temp_dir,
+    rah::config::Config,
+    std::{
+        fmt::Write as _,
+        fs,
+        path::{Path, PathBuf},
+        process::Command,
+    },
+};
+
+/// A synthetic package to put in a fake pacman database.
+#[derive(Clone)]
+pub struct FakePkg {
+    pub name: String,
+    pub version: String,
+    pub depends: Vec<String>,
+    pub provides: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub size: u64,
+}
+
+impl FakePkg {
+    pub fn new(name: &str, version: &str) -> Self {
+        FakePkg {
+            name: name.to_string(),
+            version: version.to_string(),
+            depends: Vec::new(),
+            provides: Vec::new(),
+            conflicts: Vec::new(),
+            size: 1024,
+        }
+    }
+
+    pub fn depends(mut self, deps: &[&str]) -> Self {
+        self.depends = deps.iter().map(|dep| dep.to_string()).collect();
+        self
+    }
+
+    pub fn provides(mut self, provides: &[&str]) -> Self {
+        self.provides = provides.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    pub fn conflicts(mut self, conflicts: &[&str]) -> Self {
+        self.conflicts = conflicts.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    fn dir_name(&self) -> String {
+        format!("{}-{}", self.name, self.version)
+    }
+
+    /// The package's `desc` entry, in the format shared by the local and sync databases.
+    fn desc(&self, local: bool) -> String {
+        let mut desc = String::new();
+        let mut field = |name: &str, values: &[String]| {
+            if !values.is_empty() {
+                let _ = write!(desc, "%{name}%\n{}\n\n", values.join("\n"));
+            }
+        };
+
+        if !local {
+            field(
+                "FILENAME",
+                &[format!("{}-x86_64.pkg.tar.zst", self.dir_name())],
+            );
+        }
+        field("NAME", &[self.name.clone()]);
+        field("VERSION", &[self.version.clone()]);
+        field("DESC", &[format!("Fake {} package", self.name)]);
+        field("ARCH", &[String::from("x86_64")]);
+        field("BUILDDATE", &[String::from("1700000000")]);
+        if local {
+            field("INSTALLDATE", &[String::from("1700000000")]);
+            field("SIZE", &[self.size.to_string()]);
+        } else {
+            field("CSIZE", &[(self.size / 2).to_string()]);
+            field("ISIZE", &[self.size.to_string()]);
+        }
+        field("DEPENDS", &self.depends);
+        field("PROVIDES", &self.provides);
+        field("CONFLICTS", &self.conflicts);
+
+        desc
+    }
+}
+
+/// A temporary pacman root whose local and sync databases only contain synthetic packages.
+pub struct FakeAlpmRoot {
+    root: PathBuf,
+    repos: Vec<String>,
+}
+
+impl FakeAlpmRoot {
+    pub fn new(name: &str) -> Self {
+        let root = temp_dir(&format!("alpm-{name}"));
+        let local = root.join("var/lib/pacman/local");
+        fs::create_dir_all(&local).unwrap();
+        fs::create_dir_all(root.join("var/lib/pacman/sync")).unwrap();
+        fs::write(local.join("ALPM_DB_VERSION"), "9\n").unwrap();
+
+        FakeAlpmRoot {
+            root,
+            repos: Vec::new(),
+        }
+    }
+
+    fn db_path(&self) -> PathBuf {
+        self.root.join("var/lib/pacman")
+    }
+
+    /// Mark `pkgs` as installed.
+    pub fn local(self, pkgs: &[FakePkg]) -> Self {
+        for pkg in pkgs {
+            let dir = self.db_path().join("local").join(pkg.dir_name());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("desc"), pkg.desc(true)).unwrap();
+            fs::write(dir.join("files"), "").unwrap();
+        }
+        self
+    }
+
+    /// Create a sync database named `repo` holding `pkgs`.
+    pub fn sync(mut self, repo: &str, pkgs: &[FakePkg]) -> Self {
+        let staging = self.root.join("staging").join(repo);
+        fs::create_dir_all(&staging).unwrap();
+        for pkg in pkgs {
+            let dir = staging.join(pkg.dir_name());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("desc"), pkg.desc(false)).unwrap();
+        }
+
+        let db = self.db_path().join("sync").join(format!("{repo}.db"));
+        let mut tar = Command::new("tar");
+        tar.arg("-czf").arg(&db).arg("-C").arg(&staging);
+        if pkgs.is_empty() {
+            tar.args(["-T", "/dev/null"]);
+        }
+        let status = tar
+            .args(pkgs.iter().map(|pkg| pkg.dir_name()))
+            .status()
+            .unwrap();
+        assert!(status.success(), "could not create {db:?}");
+
+        self.repos.push(repo.to_string());
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Point `conf` at this root.
+    pub fn configure(&self, conf: &mut Config) {
+        conf.pacman_root = self.root.to_string_lossy().into_owned();
+        conf.pacman_db_path = self.db_path().to_string_lossy().into_owned();
+        conf.repos = self.repos.clone();
+    }
+}
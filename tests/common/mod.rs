@@ -0,0 +1,264 @@
+// Shared fixtures for the integration tests, not every test file uses all of them
+#![allow(dead_code)]
+
+pub mod alpm_root;
+
+use {
+    alpm::Alpm,
+    rah::{
+        aur::Aur,
+        aur_scheduler::AurScheduler,
+        config::Config,
+        dep_tree::{DepTree, Pkg},
+    },
+    serde_json::{json, Value},
+    std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    },
+};
+
+/// Canned AUR packages, in the RPC's JSON format.
+pub fn aur_packages() -> Vec<Value> {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/aur_packages.json"
+    );
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// A minimal AUR lookalike serving the RPC and the metadata dump from canned packages.
+pub struct MockAur {
+    url: String,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockAur {
+    pub fn start(pkgs: Vec<Value>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                counter.fetch_add(1, Ordering::SeqCst);
+                handle(stream, &pkgs);
+            }
+        });
+
+        MockAur { url, requests }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Number of HTTP requests served so far.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// Config pointing at this mock, with a cache directory of its own.
+    pub fn config(&self, name: &str) -> Config {
+        let mut conf = Config::default();
+        conf.aur_url = self.url.clone();
+        conf.aur_cache = false;
+        conf.cache_path = temp_dir(name).to_string_lossy().into_owned();
+        conf
+    }
+}
+
+/// The dependency trees of the AUR packages `names`, resolved against `alpm` and the AUR `conf`
+/// points at. Like `-S`, dependencies nothing satisfies are an error when `strict`.
+pub async fn resolve<'a>(
+    conf: &Config,
+    alpm: &'a Alpm,
+    names: &[&str],
+    strict: bool,
+) -> anyhow::Result<Vec<DepTree<'a>>> {
+    let aur = Aur::new(conf, false).await.unwrap();
+    let mut scheduler = AurScheduler::new(&aur, 4);
+    let targets = aur
+        .info(names)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(Pkg::from)
+        .collect::<Vec<_>>();
+
+    if strict {
+        DepTree::build_all(&targets, alpm, &mut scheduler).await
+    } else {
+        DepTree::resolve_all(&targets, alpm, &mut scheduler).await
+    }
+}
+
+/// Fresh temporary directory, unique to the test `name`.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rah-test-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn handle(mut stream: TcpStream, pkgs: &[Value]) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let target = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let mut params = parse_params(query);
+    params.extend(parse_params(&String::from_utf8_lossy(&body)));
+
+    let response = if path.ends_with("packages-meta-ext-v1.json.gz") {
+        Value::Array(pkgs.to_vec())
+    } else if path.trim_end_matches('/').ends_with("rpc") {
+        rpc(&params, pkgs)
+    } else {
+        let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+        return;
+    };
+
+    let body = response.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+fn rpc(params: &[(String, String)], pkgs: &[Value]) -> Value {
+    let param = |key: &str| {
+        params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+    let args = params
+        .iter()
+        .filter(|(k, _)| k == "arg[]" || k == "arg")
+        .map(|(_, v)| v.as_str())
+        .collect::<Vec<_>>();
+
+    let results = match param("type") {
+        Some("info") | Some("multiinfo") => pkgs
+            .iter()
+            .filter(|pkg| args.contains(&pkg["Name"].as_str().unwrap()))
+            .cloned()
+            .collect::<Vec<_>>(),
+        Some("search") => {
+            let by = param("by").unwrap_or("name-desc");
+            let query = args.first().copied().unwrap_or_default();
+            pkgs.iter()
+                .filter(|pkg| search_matches(pkg, by, query))
+                .cloned()
+                .collect()
+        }
+        _ => {
+            return json!({
+                "version": 5,
+                "type": "error",
+                "resultcount": 0,
+                "results": [],
+                "error": "Incorrect request type specified."
+            })
+        }
+    };
+
+    json!({
+        "version": 5,
+        "type": if param("type") == Some("search") { "search" } else { "multiinfo" },
+        "resultcount": results.len(),
+        "results": results,
+    })
+}
+
+fn search_matches(pkg: &Value, by: &str, query: &str) -> bool {
+    let field = |name: &str| pkg[name].as_str().unwrap_or_default().to_lowercase();
+    let has_dep = |name: &str| {
+        pkg[name].as_array().unwrap().iter().any(|dep| {
+            let dep = dep.as_str().unwrap();
+            dep.split(['<', '>', '=']).next() == Some(query)
+        })
+    };
+    let query_lc = query.to_lowercase();
+
+    match by {
+        "name" => field("Name").contains(&query_lc),
+        "name-desc" => {
+            field("Name").contains(&query_lc) || field("Description").contains(&query_lc)
+        }
+        "maintainer" => field("Maintainer") == query_lc,
+        "provides" => pkg["Name"] == query || has_dep("Provides"),
+        "depends" => has_dep("Depends"),
+        "makedepends" => has_dep("MakeDepends"),
+        "optdepends" => has_dep("OptDepends"),
+        "checkdepends" => has_dep("CheckDepends"),
+        "keywords" => pkg["Keywords"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|k| k.as_str().unwrap().to_lowercase() == query_lc),
+        _ => false,
+    }
+}
+
+fn parse_params(data: &str) -> Vec<(String, String)> {
+    data.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(k), percent_decode(v))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
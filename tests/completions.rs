@@ -0,0 +1,31 @@
+use rah::{
+    cli::{self, PacmanArgs},
+    completions,
+};
+
+#[test]
+fn scripts_complete_packages_after_sync_and_remove() {
+    for shell in cli::SHELLS {
+        let mut script = Vec::new();
+        completions::generate(shell, &mut script).unwrap();
+        let script = String::from_utf8(script).unwrap();
+
+        assert!(script.contains("rah --complete-aur"), "{shell}");
+        assert!(script.contains("--sync"), "{shell}");
+        assert!(!script.contains("complete-aur <PREFIX>"), "{shell}");
+    }
+    assert!(completions::generate("tcsh", &mut Vec::new()).is_err());
+}
+
+#[test]
+fn completion_commands_are_rah_s() {
+    for args in [vec!["completions", "zsh"], vec!["--complete-aur", "yay"]] {
+        let args = args.into_iter().map(String::from).collect::<Vec<_>>();
+        assert!(PacmanArgs::parse(&cli::command(), &args).handled_by_rah());
+    }
+
+    let matches = cli::command()
+        .try_get_matches_from(["rah", "completions", "fish"])
+        .unwrap();
+    assert!(matches!(matches.subcommand(), Some(("completions", _))));
+}
@@ -0,0 +1,32 @@
+use {
+    rah::{config::Config, config_schema::KEYS},
+    std::{env, fs},
+};
+
+#[test]
+fn every_documented_key_is_handled() {
+    let toml = KEYS
+        .iter()
+        .map(|key| {
+            let value = key.default.unwrap_or(match key.kind {
+                "boolean" => "true",
+                _ => "\"/tmp/rah\"",
+            });
+            format!("{} = {value}\n", key.name)
+        })
+        .collect::<String>();
+    let path = env::temp_dir().join(format!("rah-test-{}-config.toml", std::process::id()));
+    fs::write(&path, toml).unwrap();
+
+    let mut conf = Config::default();
+    conf.parse(Some(path.to_string_lossy().into_owned()))
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let default = Config::default();
+    assert_eq!(conf.repos, default.repos);
+    assert_eq!(conf.escalation_cmd, default.escalation_cmd);
+    assert_eq!(conf.aur_cache_max_age, default.aur_cache_max_age);
+    assert_eq!(conf.build_user.as_deref(), Some("/tmp/rah"));
+    assert_eq!(conf.pgp_keyring.as_deref(), Some("/tmp/rah"));
+}
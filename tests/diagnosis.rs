@@ -0,0 +1,103 @@
+use rah::diagnosis::{self, Cause};
+
+#[test]
+fn unknown_keys_are_found() {
+    let log = "\
+==> Verifying source file signatures with gpg...
+    foo-1.0.tar.gz ... FAILED (unknown public key 1D1F0DC78F173680)
+    foo-1.0.patch ... FAILED (unknown public key 1D1F0DC78F173680)
+==> ERROR: One or more PGP signatures could not be verified!
+";
+
+    let causes = diagnosis::diagnose(log);
+
+    assert_eq!(
+        causes,
+        [Cause::UnknownKeys(vec![String::from("1D1F0DC78F173680")])]
+    );
+    assert_eq!(
+        diagnosis::unknown_keys(&causes),
+        Some([String::from("1D1F0DC78F173680")].as_slice())
+    );
+}
+
+#[test]
+fn checksum_mismatches_are_found() {
+    let log = "\
+==> Validating source files with sha256sums...
+    foo-1.0.tar.gz ... FAILED
+    foo.service ... Passed
+==> ERROR: One or more files did not pass the validity check!
+";
+
+    assert_eq!(
+        diagnosis::diagnose(log),
+        [Cause::ChecksumMismatch(vec![String::from(
+            "foo-1.0.tar.gz"
+        )])]
+    );
+}
+
+#[test]
+fn missing_dependencies_are_found() {
+    let log = "\
+==> Checking runtime dependencies...
+==> Missing dependencies:
+  -> libfoo>=2
+  -> bar
+==> Checking buildtime dependencies...
+==> ERROR: Could not resolve all dependencies.
+";
+
+    assert_eq!(
+        diagnosis::diagnose(log),
+        [Cause::MissingDependencies(vec![
+            String::from("libfoo>=2"),
+            String::from("bar")
+        ])]
+    );
+}
+
+#[test]
+fn colors_are_ignored() {
+    let log = "\x1b[1m\x1b[32m==>\x1b[0m\x1b[1m Missing dependencies:\x1b[0m
+\x1b[1m\x1b[34m  ->\x1b[0m\x1b[1m bar\x1b[0m
+tar: foo: Wrote only 4096 of 10240 bytes: No space left on device
+";
+
+    assert_eq!(
+        diagnosis::diagnose(log),
+        [
+            Cause::MissingDependencies(vec![String::from("bar")]),
+            Cause::NoSpace
+        ]
+    );
+}
+
+#[test]
+fn dead_sources_are_found() {
+    let log = "\
+==> Retrieving sources...
+  -> Downloading foo-1.0.tar.gz...
+curl: (22) The requested URL returned error: 404
+==> ERROR: Failure while downloading foo-1.0.tar.gz
+    Aborting...
+";
+
+    assert_eq!(
+        diagnosis::diagnose(log),
+        [Cause::SourceNotFound(vec![String::from("foo-1.0.tar.gz")])]
+    );
+}
+
+#[test]
+fn unknown_failures_have_no_diagnosis() {
+    let log = "\
+==> Starting build()...
+main.c:3:10: fatal error: foo.h: No such file or directory
+==> ERROR: A failure occurred in build().
+    Aborting...
+";
+
+    assert!(diagnosis::diagnose(log).is_empty());
+}
@@ -0,0 +1,58 @@
+use rah::i18n::{self, FluentArgs, Localizer, LOCALES};
+
+#[test]
+fn negotiate_posix_locales() {
+    assert_eq!(i18n::negotiate("fr_FR.UTF-8"), "fr");
+    assert_eq!(i18n::negotiate("fr_CA@euro"), "fr");
+    assert_eq!(i18n::negotiate("en_US.UTF-8"), "en");
+    assert_eq!(i18n::negotiate("de_DE.UTF-8"), "en");
+    assert_eq!(i18n::negotiate("C"), "en");
+    assert_eq!(i18n::negotiate(""), "en");
+}
+
+#[test]
+fn plurals_follow_the_locale() {
+    let found = |locale: &str, count: usize| {
+        let mut args = FluentArgs::new();
+        args.set("count", count);
+        Localizer::new(locale).tr("found-packages", Some(&args))
+    };
+
+    assert_eq!(found("en_US.UTF-8", 0), "Found 0 packages");
+    assert_eq!(found("en_US.UTF-8", 1), "Found 1 package");
+    assert_eq!(found("en_US.UTF-8", 2), "Found 2 packages");
+    // french uses the singular for 0 too
+    assert_eq!(found("fr_FR.UTF-8", 0), "0 paquet trouvé");
+    assert_eq!(found("fr_FR.UTF-8", 1), "1 paquet trouvé");
+    assert_eq!(found("fr_FR.UTF-8", 2), "2 paquets trouvés");
+}
+
+#[test]
+fn missing_messages_fall_back() {
+    let fr = Localizer::new("fr_FR.UTF-8");
+    assert_eq!(fr.tr("no-such-message", None), "no-such-message");
+    assert_eq!(
+        Localizer::new("C").tr("arg-format", None),
+        "Output format : text, json, ndjson or a template like '{name}'"
+    );
+}
+
+#[test]
+fn every_locale_is_complete() {
+    let english = Localizer::new("en");
+    let ids = LOCALES[0]
+        .1
+        .lines()
+        .filter(|line| !line.starts_with([' ', '#']))
+        .filter_map(|line| line.split_once(" ="))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    assert!(ids.iter().all(|id| english.has_message(id)));
+
+    for (locale, _) in LOCALES {
+        let localizer = Localizer::new(locale);
+        for id in &ids {
+            assert!(localizer.has_message(id), "{locale} lacks {id}");
+        }
+    }
+}
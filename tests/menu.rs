@@ -0,0 +1,20 @@
+use rah::menu::parse_selection;
+
+#[test]
+fn selections_combine_numbers_ranges_and_exclusions() {
+    assert_eq!(parse_selection("1 3-5 ^4", 10).unwrap(), [1, 3, 5]);
+    assert_eq!(parse_selection("2,2, 1\n", 3).unwrap(), [1, 2]);
+    assert_eq!(parse_selection("5-3", 5).unwrap(), [3, 4, 5]);
+    assert_eq!(parse_selection("^2 ^4-5", 5).unwrap(), [1, 3]);
+    assert!(parse_selection("  \n", 5).unwrap().is_empty());
+}
+
+#[test]
+fn invalid_selections_are_rejected() {
+    for input in ["0", "6", "a", "1-", "^x", "2-9"] {
+        assert!(
+            parse_selection(input, 5).is_err(),
+            "{input} should be rejected"
+        );
+    }
+}
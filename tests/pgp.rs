@@ -0,0 +1,80 @@
+mod common;
+
+use {
+    common::temp_dir,
+    rah::{
+        config::Config,
+        pgp::{self, TrustedKeys},
+    },
+};
+
+#[test]
+fn validpgpkeys_are_read_from_srcinfo() {
+    let srcinfo = "\
+pkgbase = foo
+\tpkgver = 1.0
+\tsource = https://example.org/foo-1.0.tar.gz
+\tsource = https://example.org/foo-1.0.tar.gz.sig
+\tvalidpgpkeys = 1D1F0DC78F173680
+\tvalidpgpkeys = A2C794A986419D8A
+\tvalidpgpkeys = 1D1F0DC78F173680
+
+pkgname = foo
+";
+
+    assert_eq!(
+        pgp::parse_srcinfo_keys(srcinfo),
+        ["1D1F0DC78F173680", "A2C794A986419D8A"]
+    );
+    assert!(pgp::parse_srcinfo_keys("pkgbase = foo\n").is_empty());
+}
+
+#[test]
+fn keys_missing_from_an_export_are_found() {
+    let listing = b"\
+pub:-:4096:1:1D1F0DC78F173680:1500000000:::-:::scESC::::::23::0:
+fpr:::::::::0123456789ABCDEF012345671D1F0DC78F173680:
+uid:-::::1500000000::0123456789ABCDEF0123456789ABCDEF01234567::Foo <foo@example.org>::::::::::0:
+sub:-:4096:1:9A8B7C6D5E4F3A2B:1500000000::::::e::::::23:
+fpr:::::::::FEDCBA9876543210012345679A8B7C6D5E4F3A2B:
+";
+    let keys = [
+        String::from("0123456789ABCDEF012345671D1F0DC78F173680"),
+        String::from("9a8b7c6d5e4f3a2b"),
+        String::from("A2C794A986419D8A"),
+    ];
+
+    assert_eq!(
+        pgp::missing_from_listing(&keys, listing),
+        ["A2C794A986419D8A"]
+    );
+    assert_eq!(pgp::missing_from_listing(&keys[..1], b""), keys[..1]);
+}
+
+#[test]
+fn uids_are_unescaped() {
+    let keyring =
+        b"uid:-::::1500000000::0123::Ren\\xc3\\xa9 \\x3a) <rene@example.org>::::::::::0:\n";
+    let keyserver = b"uid:Ren%C3%A9%20%3A%29%20%3Crene%40example.org%3E:1500000000::\n";
+
+    let uid = "Ren\u{e9} :) <rene@example.org>";
+    assert_eq!(pgp::colons_uid(keyring, 9).as_deref(), Some(uid));
+    assert_eq!(pgp::colons_uid(keyserver, 1).as_deref(), Some(uid));
+    assert_eq!(pgp::colons_uid(b"pub:-:4096\n", 9), None);
+}
+
+#[test]
+fn trusted_keys_are_kept_per_pkgbase() {
+    let mut conf = Config::default();
+    conf.db_path = temp_dir("pgp").to_string_lossy().into_owned();
+    let keys = [String::from("1D1F0DC78F173680")];
+
+    let mut trusted = TrustedKeys::load(&conf).unwrap();
+    assert!(!trusted.is_trusted("foo", &keys[0]));
+    trusted.trust("foo", &keys);
+    trusted.save(&conf).unwrap();
+
+    let trusted = TrustedKeys::load(&conf).unwrap();
+    assert!(trusted.is_trusted("foo", &keys[0]));
+    assert!(!trusted.is_trusted("bar", &keys[0]));
+}
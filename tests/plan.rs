@@ -0,0 +1,224 @@
+mod common;
+
+use {
+    common::{
+        alpm_root::{FakeAlpmRoot, FakePkg},
+        aur_packages, resolve, MockAur,
+    },
+    rah::{
+        dep_tree::{Conflict, DepType},
+        helpers,
+        plan::{self, Plan, PlanDep, PlanPkg, Resolution},
+    },
+    std::collections::HashMap,
+};
+
+#[test]
+fn dependencies_are_built_first() {
+    let deps = HashMap::from([
+        (
+            String::from("app"),
+            vec![String::from("lib"), String::from("tool")],
+        ),
+        (String::from("lib"), vec![String::from("tool")]),
+        (String::from("other"), vec![String::from("lib")]),
+    ]);
+
+    assert_eq!(
+        plan::build_order(&[String::from("app"), String::from("other")], &deps),
+        ["tool", "lib", "app", "other"]
+    );
+}
+
+#[tokio::test]
+async fn plan_groups_the_transaction() {
+    let mock = MockAur::start(aur_packages());
+    let root = FakeAlpmRoot::new("plan")
+        .local(&[
+            FakePkg::new("glibc", "2.39-1"),
+            FakePkg::new("oldtool", "1.0-1"),
+            FakePkg::new("legacy", "1.0-1").conflicts(&["libfoo.so"]),
+        ])
+        .sync(
+            "extra",
+            &[FakePkg::new("newtool", "2.0-1").conflicts(&["oldtool", "libfoo"])],
+        );
+    let mut conf = mock.config("plan");
+    root.configure(&mut conf);
+
+    let alpm = helpers::alpm(&conf).unwrap();
+    let trees = resolve(&conf, &alpm, &["rah-test-app"], true)
+        .await
+        .unwrap();
+    let repo_targets = [helpers::find_sync_pkg(&alpm, "extra/newtool").unwrap()];
+
+    let plan = Plan::new(&trees, &repo_targets, &alpm);
+
+    let repo = plan
+        .repo
+        .iter()
+        .map(|pkg| (pkg.name.as_str(), pkg.reason.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(repo, [("newtool", "target")]);
+    assert_eq!((plan.download_size, plan.installed_size), (512, 1024));
+
+    let aur = plan
+        .aur
+        .iter()
+        .map(|pkg| (pkg.name.as_str(), pkg.reason.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(aur.len(), 3);
+    assert_eq!(aur[2], ("rah-test-app", "target"));
+    assert!(aur.contains(&("libfoo", "depends")));
+    assert!(aur.contains(&("rah-test-build-tool", "make_depends")));
+    assert_eq!(plan.remove_after, ["rah-test-build-tool"]);
+
+    let conflicts = plan
+        .conflicts
+        .iter()
+        .map(|c| (c.conflicts_with.as_str(), c.installed))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        conflicts,
+        [("oldtool", true), ("libfoo", false), ("legacy", true)]
+    );
+    assert_eq!(plan.conflicts[2].package, "libfoo");
+}
+
+fn planned(name: &str, reason: &str, size: Option<i64>) -> PlanPkg {
+    PlanPkg {
+        name: name.to_string(),
+        version: String::from("1.0-1"),
+        base: None,
+        repo: size.map(|_| String::from("extra")),
+        reason: reason.to_string(),
+        aur_depends: Vec::new(),
+        download_size: size,
+        installed_size: size,
+    }
+}
+
+fn conflict(package: &str, with: &str) -> Conflict {
+    Conflict {
+        package: package.to_string(),
+        conflicts_with: with.to_string(),
+        installed: true,
+    }
+}
+
+fn conflicting_plan() -> Plan {
+    Plan {
+        repo: vec![planned("tool", "target", Some(100))],
+        aur: vec![
+            planned("libfoo-git", "depends", None),
+            planned("app", "target", None),
+        ],
+        conflicts: vec![
+            conflict("tool", "oldtool"),
+            conflict("libfoo-git", "libfoo"),
+        ],
+        download_size: 100,
+        installed_size: 100,
+        ..Plan::default()
+    }
+}
+
+#[test]
+fn skipped_conflicts_leave_the_plan() {
+    let mut plan = conflicting_plan();
+    let mut asked = Vec::new();
+
+    plan.resolve_conflicts(|conflict, can_skip| {
+        asked.push((conflict.package.clone(), can_skip));
+        Ok(match conflict.package.as_str() {
+            "tool" => Resolution::Skip,
+            _ => Resolution::Replace,
+        })
+    })
+    .unwrap();
+
+    // only targets can be left out
+    assert_eq!(
+        asked,
+        [
+            (String::from("tool"), true),
+            (String::from("libfoo-git"), false)
+        ]
+    );
+    assert!(plan.repo.is_empty());
+    assert_eq!(plan.download_size, 0);
+    assert_eq!(plan.conflicts.len(), 1);
+    assert!(plan.replaces("libfoo-git"));
+    assert!(!plan.replaces("tool"));
+}
+
+fn dep(package: &str, dependency: &str, dep_type: DepType) -> PlanDep {
+    PlanDep {
+        package: package.to_string(),
+        dependency: dependency.to_string(),
+        dep_type,
+    }
+}
+
+#[test]
+fn skipped_targets_take_their_dependencies_along() {
+    let mut plan = Plan {
+        repo: vec![
+            planned("tool", "target", Some(100)),
+            planned("zlib", "depends", Some(10)),
+            planned("cmake", "make_depends", Some(20)),
+            planned("lint", "depends", Some(5)),
+        ],
+        aur: vec![
+            planned("libfoo-git", "depends", None),
+            planned("app", "target", None),
+        ],
+        remove_after: vec![String::from("cmake")],
+        conflicts: vec![conflict("app", "oldapp"), conflict("libfoo-git", "libfoo")],
+        download_size: 135,
+        installed_size: 135,
+        deps: vec![
+            dep("app", "libfoo-git", DepType::Dep),
+            dep("app", "cmake", DepType::MakeDep),
+            dep("app", "lint", DepType::Dep),
+            dep("libfoo-git", "zlib", DepType::Dep),
+            dep("tool", "zlib", DepType::Dep),
+            dep("tool", "lint", DepType::CheckDep),
+        ],
+        ..Plan::default()
+    };
+    let mut asked = Vec::new();
+
+    plan.resolve_conflicts(|conflict, _| {
+        asked.push(conflict.package.clone());
+        Ok(Resolution::Skip)
+    })
+    .unwrap();
+
+    // libfoo-git went with app, nothing to ask about it
+    assert_eq!(asked, ["app"]);
+    assert!(plan.aur.is_empty());
+    let repo = plan
+        .repo
+        .iter()
+        .map(|pkg| pkg.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(repo, ["tool", "zlib", "lint"]);
+    assert_eq!(plan.download_size, 115);
+    assert_eq!(plan.installed_size, 115);
+    assert!(plan.conflicts.is_empty());
+    // only tool needs lint now, to run its checks
+    assert_eq!(plan.repo[2].reason, "check_depends");
+    assert_eq!(plan.remove_after, ["lint"]);
+}
+
+#[test]
+fn aborting_a_conflict_fails() {
+    let mut plan = conflicting_plan();
+
+    let err = plan
+        .resolve_conflicts(|_, _| Ok(Resolution::Abort))
+        .unwrap_err();
+
+    assert!(err.to_string().contains("oldtool"));
+}
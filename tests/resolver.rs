@@ -0,0 +1,186 @@
+mod common;
+
+use {
+    common::{
+        alpm_root::{FakeAlpmRoot, FakePkg},
+        aur_packages, resolve, MockAur,
+    },
+    rah::{
+        aur::Aur,
+        dep_graph,
+        dep_tree::{DepTree, DepType, Pkg},
+        helpers,
+        sync::{search_flags, SearchFlag},
+    },
+};
+
+/// (name, dependency type, installed, from the AUR) of the direct dependencies of `tree`.
+fn leaves(tree: &DepTree) -> Vec<(String, DepType, bool, bool)> {
+    let mut leaves = tree
+        .leaves()
+        .unwrap()
+        .iter()
+        .map(|leave| {
+            (
+                leave.pkg().name(),
+                leave.dep_type(),
+                leave.installed(),
+                matches!(leave.pkg(), Pkg::Aur { .. }),
+            )
+        })
+        .collect::<Vec<_>>();
+    leaves.sort_by(|a, b| a.0.cmp(&b.0));
+    leaves
+}
+
+fn base_system(name: &str) -> FakeAlpmRoot {
+    FakeAlpmRoot::new(name)
+        .local(&[FakePkg::new("glibc", "2.39-1")])
+        .sync("core", &[FakePkg::new("glibc", "2.40-1")])
+}
+
+#[tokio::test]
+async fn aur_dependencies_are_pulled_from_the_aur() {
+    let mock = MockAur::start(aur_packages());
+    let root = base_system("aur-deps");
+    let mut conf = mock.config("aur-deps");
+    root.configure(&mut conf);
+
+    let alpm = helpers::alpm(&conf).unwrap();
+    let trees = resolve(&conf, &alpm, &["rah-test-app"], true)
+        .await
+        .unwrap();
+
+    assert_eq!(trees.len(), 1);
+    assert_eq!(
+        leaves(&trees[0]),
+        [
+            (String::from("glibc"), DepType::Dep, true, false),
+            (String::from("libfoo"), DepType::Dep, false, true),
+            (
+                String::from("rah-test-build-tool"),
+                DepType::MakeDep,
+                false,
+                true
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn installed_and_repo_packages_are_preferred() {
+    let mock = MockAur::start(aur_packages());
+    let root = FakeAlpmRoot::new("repo-deps")
+        .local(&[
+            FakePkg::new("glibc", "2.39-1"),
+            FakePkg::new("libfoo-git", "r120.def5678-1").provides(&["libfoo=2.3"]),
+        ])
+        .sync(
+            "extra",
+            &[FakePkg::new("build-tools", "1.0-1").provides(&["rah-test-build-tool=0.4"])],
+        );
+    let mut conf = mock.config("repo-deps");
+    root.configure(&mut conf);
+
+    let alpm = helpers::alpm(&conf).unwrap();
+    let trees = resolve(&conf, &alpm, &["rah-test-app"], true)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        leaves(&trees[0]),
+        [
+            (String::from("build-tools"), DepType::MakeDep, false, false),
+            (String::from("glibc"), DepType::Dep, true, false),
+            (String::from("libfoo-git"), DepType::Dep, true, false),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unsatisfiable_dependencies_are_reported() {
+    let mock = MockAur::start(aur_packages());
+    let root = base_system("missing-deps");
+    let mut conf = mock.config("missing-deps");
+    root.configure(&mut conf);
+
+    let alpm = helpers::alpm(&conf).unwrap();
+    let err = resolve(&conf, &alpm, &["rah-test-broken"], true)
+        .await
+        .err()
+        .unwrap();
+
+    assert!(err.to_string().contains("rah-test-nonexistent"));
+}
+
+#[tokio::test]
+async fn missing_dependencies_are_kept_in_the_graph() {
+    let mock = MockAur::start(aur_packages());
+    let root = base_system("graph");
+    let mut conf = mock.config("graph");
+    root.configure(&mut conf);
+
+    let alpm = helpers::alpm(&conf).unwrap();
+    let trees = resolve(&conf, &alpm, &["rah-test-app", "rah-test-broken"], false)
+        .await
+        .unwrap();
+    let broken = trees
+        .iter()
+        .find(|tree| tree.pkg().name() == "rah-test-broken")
+        .unwrap();
+    assert_eq!(
+        broken.missing(),
+        [(String::from("rah-test-nonexistent"), DepType::Dep)]
+    );
+
+    let dot = dep_graph::dot(&trees);
+    assert!(dot.starts_with("digraph dependencies {"));
+    assert!(dot.contains("\"rah-test-app\" -> \"libfoo\" [label=\"depends\"];"));
+    assert!(dot.contains(
+        "\"rah-test-app\" -> \"rah-test-build-tool\" [label=\"make_depends\" style=dashed];"
+    ));
+    assert!(dot.contains("\"rah-test-nonexistent\" [shape=octagon color=red fontcolor=red];"));
+}
+
+#[tokio::test]
+async fn search_flags_reflect_local_packages() {
+    let mock = MockAur::start(aur_packages());
+    let root = FakeAlpmRoot::new("search-flags").local(&[
+        FakePkg::new("rah-test-app", "1.0-1"),
+        FakePkg::new("libfoo", "2.0-3"),
+    ]);
+    let mut conf = mock.config("search-flags");
+    root.configure(&mut conf);
+
+    let alpm = helpers::alpm(&conf).unwrap();
+    let aur = Aur::new(&conf, false).await.unwrap();
+    let hits = aur
+        .info(&[
+            "rah-test-app",
+            "libfoo",
+            "rah-test-outdated",
+            "rah-test-build-tool",
+        ])
+        .await
+        .unwrap();
+    let flags = |name: &str| {
+        search_flags(
+            hits.iter().find(|pkg| pkg.name == name).unwrap(),
+            alpm.localdb(),
+        )
+    };
+
+    assert_eq!(flags("rah-test-app"), [SearchFlag::Installed]);
+    assert_eq!(
+        flags("libfoo"),
+        [SearchFlag::OtherVersionInstalled(String::from("2.0-3"))]
+    );
+    assert_eq!(
+        flags("rah-test-outdated"),
+        [SearchFlag::OutOfDate {
+            since: 1710000000,
+            last_modified: 1700007000,
+        }]
+    );
+    assert!(flags("rah-test-build-tool").is_empty());
+}
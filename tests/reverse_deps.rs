@@ -0,0 +1,62 @@
+mod common;
+
+use {
+    common::{
+        alpm_root::{FakeAlpmRoot, FakePkg},
+        aur_packages, MockAur,
+    },
+    rah::reverse_deps,
+};
+
+fn system(name: &str) -> FakeAlpmRoot {
+    FakeAlpmRoot::new(name)
+        .local(&[
+            FakePkg::new("glibc", "2.39-1"),
+            FakePkg::new("libfoo", "2.1-1"),
+            FakePkg::new("rah-test-build-tool", "0.3-2"),
+            FakePkg::new("rah-test-app", "1.0-1").depends(&["libfoo>=2.0", "glibc"]),
+            FakePkg::new("frontend", "0.1-1").depends(&["rah-test-app"]),
+        ])
+        .sync("core", &[FakePkg::new("glibc", "2.40-1")])
+}
+
+#[tokio::test]
+async fn dependents_are_followed_transitively() {
+    let mock = MockAur::start(aur_packages());
+    let root = system("reverse-deps");
+    let mut conf = mock.config("reverse-deps");
+    root.configure(&mut conf);
+
+    let found = reverse_deps::dependents("libfoo", &conf).await.unwrap();
+    let found = found
+        .iter()
+        .map(|dep| (dep.name.as_str(), dep.requires.as_str(), dep.depth))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        [
+            ("rah-test-app", "libfoo", 1),
+            ("frontend", "rah-test-app", 2)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn make_dependencies_come_from_the_aur() {
+    let mock = MockAur::start(aur_packages());
+    let root = system("reverse-make-deps");
+    let mut conf = mock.config("reverse-make-deps");
+    root.configure(&mut conf);
+
+    let found = reverse_deps::dependents("rah-test-build-tool", &conf)
+        .await
+        .unwrap();
+
+    assert_eq!(found.len(), 2);
+    assert_eq!(
+        (found[0].name.as_str(), found[0].reason, found[0].installed),
+        ("rah-test-app", "make_depends", true)
+    );
+    assert_eq!(found[1].name, "frontend");
+}
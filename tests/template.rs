@@ -0,0 +1,64 @@
+use {rah::template::Template, serde_json::json};
+
+fn render(template: &str, color: bool) -> String {
+    let record = json!({
+        "name": "libfoo",
+        "version": "2.1-1",
+        "votes": 42,
+        "maintainer": null,
+        "out_of_date": 1710000000,
+        "provides": ["libfoo.so", "foo"],
+    });
+    Template::parse(template)
+        .unwrap()
+        .render(&record, color)
+        .unwrap()
+}
+
+#[test]
+fn fields_are_substituted() {
+    assert_eq!(
+        render("{name} {version} ({votes}) {provides}", false),
+        "libfoo 2.1-1 (42) libfoo.so, foo"
+    );
+    assert_eq!(render("{{{name}}}\\t{maintainer}|", false), "{libfoo}\t|");
+}
+
+#[test]
+fn conditionals_depend_on_presence() {
+    let template =
+        "{name}{?maintainer} by {maintainer}{/maintainer}{!maintainer} (orphan){/maintainer}";
+    assert_eq!(render(template, false), "libfoo (orphan)");
+    assert_eq!(
+        render(
+            "{?out_of_date}out of date since {out_of_date:%Y-%m-%d}{/out_of_date}",
+            false
+        ),
+        "out of date since 2024-03-09"
+    );
+}
+
+#[test]
+fn colors_are_optional() {
+    assert_eq!(render("{$bold}{name}{$clear}", false), "libfoo");
+    assert_eq!(render("{$red}{name}", true), "\x1b[31mlibfoo\x1b[0m");
+}
+
+#[test]
+fn invalid_templates_are_rejected() {
+    for template in [
+        "{name",
+        "name}",
+        "{?name}x",
+        "{?name}x{/version}",
+        "{$pink}",
+    ] {
+        assert!(
+            Template::parse(template).is_err(),
+            "{template} should be rejected"
+        );
+    }
+
+    let template = Template::parse("{nope}").unwrap();
+    assert!(template.render(&json!({"name": "x"}), false).is_err());
+}
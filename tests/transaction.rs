@@ -0,0 +1,109 @@
+mod common;
+
+use {
+    common::temp_dir,
+    rah::{
+        config::Config,
+        plan::{Plan, PlanPkg},
+        transaction::{Built, Transaction},
+    },
+    std::path::PathBuf,
+};
+
+fn aur_pkg(name: &str, base: &str, reason: &str, aur_depends: &[&str]) -> PlanPkg {
+    PlanPkg {
+        name: name.to_string(),
+        version: String::from("1.0-1"),
+        base: Some(base.to_string()),
+        repo: None,
+        reason: reason.to_string(),
+        aur_depends: aur_depends.iter().map(|dep| dep.to_string()).collect(),
+        download_size: None,
+        installed_size: None,
+    }
+}
+
+/// lib <- tool <- app, split-a and split-b sharing a base, and other standing alone.
+fn transaction() -> Transaction {
+    let plan = Plan {
+        aur: vec![
+            aur_pkg("lib", "lib", "depends", &[]),
+            aur_pkg("split-a", "split", "depends", &[]),
+            aur_pkg("tool", "tool", "make_depends", &["lib"]),
+            aur_pkg("split-b", "split", "depends", &["split-a"]),
+            aur_pkg("app", "app", "target", &["tool", "split-b"]),
+            aur_pkg("other", "other", "target", &[]),
+        ],
+        ..Plan::default()
+    };
+    Transaction::new(plan, vec![String::from("--needed")])
+}
+
+#[test]
+fn pending_builds_follow_the_plan() {
+    let mut transaction = transaction();
+    assert_eq!(
+        transaction.pending(),
+        ["lib", "split", "tool", "app", "other"]
+    );
+
+    transaction.built.push(Built {
+        pkgbase: String::from("lib"),
+        paths: vec![PathBuf::from("/tmp/lib-1.0-1-x86_64.pkg.tar.zst")],
+    });
+    assert_eq!(transaction.pending(), ["split", "tool", "app", "other"]);
+}
+
+fn built(transaction: &mut Transaction, pkgbase: &str) {
+    transaction.built.push(Built {
+        pkgbase: pkgbase.to_string(),
+        paths: Vec::new(),
+    });
+}
+
+#[test]
+fn independent_packages_are_built_together() {
+    let mut transaction = transaction();
+    assert_eq!(transaction.ready(), ["lib", "split", "other"]);
+
+    built(&mut transaction, "lib");
+    assert_eq!(transaction.ready(), ["split", "tool", "other"]);
+
+    built(&mut transaction, "split");
+    built(&mut transaction, "tool");
+    built(&mut transaction, "other");
+    assert_eq!(transaction.ready(), ["app"]);
+
+    built(&mut transaction, "app");
+    assert!(transaction.ready().is_empty());
+}
+
+#[test]
+fn skipping_leaves_dependents_out() {
+    let mut transaction = transaction();
+
+    transaction.skip("lib");
+
+    assert_eq!(transaction.skipped, ["lib", "tool", "app"]);
+    assert_eq!(transaction.pending(), ["split", "other"]);
+}
+
+#[test]
+fn state_survives_a_restart() {
+    let mut conf = Config::default();
+    conf.db_path = temp_dir("transaction").to_string_lossy().into_owned();
+    assert!(Transaction::load(&conf).unwrap().is_none());
+
+    let mut transaction = transaction();
+    transaction.repo_done = true;
+    transaction.skip("split");
+    transaction.save(&conf).unwrap();
+
+    let loaded = Transaction::load(&conf).unwrap().unwrap();
+    assert!(loaded.repo_done);
+    assert_eq!(loaded.pacman_flags, ["--needed"]);
+    assert_eq!(loaded.pending(), transaction.pending());
+
+    Transaction::clear(&conf).unwrap();
+    assert!(Transaction::load(&conf).unwrap().is_none());
+}